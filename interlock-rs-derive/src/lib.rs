@@ -0,0 +1,243 @@
+//! `#[derive(Interlockable)]` for interlock-rs. use it through the `derive` feature of
+//! `interlock-rs` rather than depending on this crate directly.
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{
+    parse_macro_input, Attribute, Data, DeriveInput, Error, Fields, GenericArgument, Lit, Meta,
+    NestedMeta, Path, PathArguments, Result, Type,
+};
+
+/// derive `Interlockable` for a struct of `Cell`s.
+///
+/// `set` and `clear` write every field, so `UpdateType` is the value type of the single field, or
+/// a tuple of them (in declaration order) for several fields. fields that shouldn't be written
+/// (limits, names, ...) are marked `#[interlock(skip)]`, and don't have to be `Cell`s.
+///
+/// `is_clear` comes from one of these on the struct:
+///   * `#[interlock(clear_when = "...")]`: an expression, which can use `self`
+///   * `#[interlock(clear_fn = "...")]`: the path of a `fn(&Self) -> bool`
+///
+/// ```ignore
+/// #[derive(Interlockable)]
+/// #[interlock(clear_when = "self.pressure.get() < self.limit")]
+/// struct Vessel {
+///     pressure: Cell<f32>,
+///     #[interlock(skip)]
+///     limit: f32,
+/// }
+/// ```
+#[proc_macro_derive(Interlockable, attributes(interlock))]
+pub fn derive_interlockable(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// how `is_clear` is generated
+enum Predicate {
+    Expr(TokenStream),
+    Fn(Path),
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream> {
+    let predicate = predicate(&input)?;
+    let fields = match &input.data {
+        Data::Struct(s) => &s.fields,
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "Interlockable can only be derived for structs",
+            ))
+        }
+    };
+
+    // (member, value type) of every field that `set` / `clear` write
+    let mut updated = Vec::new();
+    for (i, field) in fields.iter().enumerate() {
+        if skipped(&field.attrs)? {
+            continue;
+        }
+        let member = match &field.ident {
+            Some(ident) => quote!(#ident),
+            None => {
+                let index = syn::Index::from(i);
+                quote!(#index)
+            }
+        };
+        updated.push((member, cell_value_type(&field.ty)?));
+    }
+    if updated.is_empty() && !matches!(fields, Fields::Unit) {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "Interlockable needs at least one field that isn't skipped",
+        ));
+    }
+
+    let (update_type, assign) = match updated.as_slice() {
+        [(member, ty)] => (quote!(#ty), quote!(self.#member.set(new);)),
+        _ => {
+            let types = updated.iter().map(|(_, ty)| ty);
+            let members = updated.iter().map(|(member, _)| member);
+            let values = (0..updated.len()).map(|i| format_ident!("v{}", i));
+            let values2 = values.clone();
+            (
+                quote!((#(#types,)*)),
+                quote! {
+                    let (#(#values,)*) = new;
+                    #(self.#members.set(#values2);)*
+                },
+            )
+        }
+    };
+
+    let is_clear = match predicate {
+        Predicate::Expr(expr) => quote!(#expr),
+        Predicate::Fn(path) => quote!(#path(self)),
+    };
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::interlock_rs::Interlockable for #ident #ty_generics #where_clause {
+            type UpdateType = #update_type;
+
+            fn is_clear(&self) -> bool {
+                #is_clear
+            }
+
+            fn set(&self, new: Self::UpdateType) {
+                #assign
+            }
+
+            fn clear(&self, new: Self::UpdateType) {
+                #assign
+            }
+        }
+    })
+}
+
+/// the `#[interlock(...)]` attributes, as (name, value) pairs
+fn interlock_args(attrs: &[Attribute]) -> Result<Vec<(Path, Option<Lit>)>> {
+    let mut args = Vec::new();
+    for attr in attrs.iter().filter(|a| a.path.is_ident("interlock")) {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => return Err(Error::new_spanned(meta, "expected `#[interlock(...)]`")),
+        };
+        for nested in list.nested {
+            match nested {
+                NestedMeta::Meta(Meta::Path(path)) => args.push((path, None)),
+                NestedMeta::Meta(Meta::NameValue(nv)) => args.push((nv.path, Some(nv.lit))),
+                other => return Err(Error::new_spanned(other, "unexpected interlock argument")),
+            }
+        }
+    }
+    Ok(args)
+}
+
+fn predicate(input: &DeriveInput) -> Result<Predicate> {
+    let mut predicate = None;
+    for (path, lit) in interlock_args(&input.attrs)? {
+        let s = match (&lit, path.get_ident()) {
+            (Some(Lit::Str(s)), Some(_)) => s,
+            _ => {
+                return Err(Error::new_spanned(
+                    path,
+                    "expected `clear_when = \"...\"` or `clear_fn = \"...\"`",
+                ))
+            }
+        };
+        let parsed = match path.get_ident().map(|i| i.to_string()).as_deref() {
+            Some("clear_when") => Predicate::Expr(s.parse()?),
+            Some("clear_fn") => Predicate::Fn(s.parse()?),
+            _ => return Err(Error::new_spanned(path, "unknown interlock argument")),
+        };
+        if predicate.replace(parsed).is_some() {
+            return Err(Error::new_spanned(
+                path,
+                "only one of `clear_when` and `clear_fn` can be given",
+            ));
+        }
+    }
+    predicate.ok_or_else(|| {
+        Error::new_spanned(
+            &input.ident,
+            "missing `#[interlock(clear_when = \"...\")]` or `#[interlock(clear_fn = \"...\")]`",
+        )
+    })
+}
+
+fn skipped(attrs: &[Attribute]) -> Result<bool> {
+    let mut skip = false;
+    for (path, lit) in interlock_args(attrs)? {
+        match (path.is_ident("skip"), lit) {
+            (true, None) => skip = true,
+            _ => return Err(Error::new_spanned(path, "expected `#[interlock(skip)]`")),
+        }
+    }
+    Ok(skip)
+}
+
+/// get `T` out of a `Cell<T>` field type
+fn cell_value_type(ty: &Type) -> Result<&Type> {
+    let err = || {
+        Error::new(
+            ty.span(),
+            "Interlockable fields must be `Cell`s, or marked `#[interlock(skip)]`",
+        )
+    };
+    let segment = match ty {
+        Type::Path(p) if p.qself.is_none() => p.path.segments.last().ok_or_else(err)?,
+        _ => return Err(err()),
+    };
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) if segment.ident == "Cell" && args.args.len() == 1 => {
+            match args.args.first() {
+                Some(GenericArgument::Type(inner)) => Ok(inner),
+                _ => Err(err()),
+            }
+        }
+        _ => Err(err()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    #[test]
+    /// test that several fields make a tuple update type, and skipped fields are left alone
+    fn multi_field() {
+        let input: DeriveInput = parse_quote! {
+            #[interlock(clear_when = "self.a.get() && !self.b.get()")]
+            struct S {
+                a: Cell<bool>,
+                #[interlock(skip)]
+                name: &'static str,
+                b: core::cell::Cell<bool>,
+            }
+        };
+        let out = expand(input).unwrap().to_string();
+        assert!(out.contains("type UpdateType = (bool , bool ,)"));
+        assert!(!out.contains("self . name"));
+    }
+
+    #[test]
+    /// test that bad input is reported instead of generating a broken impl
+    fn errors() {
+        let missing: DeriveInput = parse_quote! {
+            struct S { a: Cell<bool> }
+        };
+        assert!(expand(missing).is_err());
+
+        let not_cell: DeriveInput = parse_quote! {
+            #[interlock(clear_fn = "ok")]
+            struct S { a: bool }
+        };
+        assert!(expand(not_cell).is_err());
+    }
+}
@@ -0,0 +1,103 @@
+//! ISA-18.2 style alarm states.
+//!
+//! [`InterlockState`] only says whether an interlock is asserted. [`AlarmState`] adds what an
+//! operator needs to know on top of that: whether the trip has been acknowledged, whether it has
+//! returned to normal without being acknowledged, and whether the alarm is out of service. see
+//! [`Interlock::get_alarm_state`](crate::Interlock::get_alarm_state).
+
+use crate::InterlockState;
+
+/// the alarm state of an interlock
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlarmState {
+    /// not tripped, nothing to acknowledge
+    Normal,
+    /// tripped, and not acknowledged yet
+    TrippedUnacked,
+    /// tripped, and acknowledged
+    TrippedAcked,
+    /// no longer tripped, but the trip was never acknowledged
+    RtnUnacked,
+    /// temporarily taken out of service by an operator. the interlock itself still trips
+    Shelved,
+    /// taken out of service by design or by other logic. the interlock itself still trips
+    Suppressed,
+}
+
+impl From<AlarmState> for InterlockState {
+    /// the tripped states convert to [`InterlockState::Active`], everything else to
+    /// [`InterlockState::Inactive`]. out of service alarms don't say anything about the
+    /// interlock, so check [`Interlock::get_state`](crate::Interlock::get_state) for those
+    fn from(value: AlarmState) -> Self {
+        match value {
+            AlarmState::TrippedUnacked | AlarmState::TrippedAcked => InterlockState::Active,
+            AlarmState::Normal
+            | AlarmState::RtnUnacked
+            | AlarmState::Shelved
+            | AlarmState::Suppressed => InterlockState::Inactive,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::InterlockableBool;
+    use crate::time::ManualClock;
+    use crate::{Interlock, LatchMode};
+
+    #[test]
+    /// test acknowledging a trip before and after it is cleared
+    fn acknowledge() {
+        let i1 = Interlock::new(InterlockableBool::new(false));
+        assert_eq!(i1.get_alarm_state(), AlarmState::Normal);
+        i1.set(true);
+        assert_eq!(i1.get_alarm_state(), AlarmState::TrippedUnacked);
+        i1.acknowledge();
+        assert_eq!(i1.get_alarm_state(), AlarmState::TrippedAcked);
+        i1.set(false);
+        i1.try_clear_interlock().unwrap();
+        assert_eq!(i1.get_alarm_state(), AlarmState::Normal);
+
+        let i2 =
+            Interlock::new(InterlockableBool::new(false)).with_latch_mode(LatchMode::AutoReset);
+        i2.set(true);
+        i2.set(false);
+        assert_eq!(i2.get_alarm_state(), AlarmState::RtnUnacked);
+        i2.acknowledge();
+        assert_eq!(i2.get_alarm_state(), AlarmState::Normal);
+    }
+
+    #[test]
+    /// test that shelving hides the alarm but not the trip, and that unshelving re-annunciates
+    fn shelve() {
+        let i1 = Interlock::new(InterlockableBool::new(false));
+        i1.shelve();
+        i1.set(true);
+        assert_eq!(i1.get_alarm_state(), AlarmState::Shelved);
+        assert_eq!(i1.get_state(), InterlockState::Active);
+        i1.suppress();
+        assert_eq!(i1.get_alarm_state(), AlarmState::Suppressed);
+        i1.unsuppress();
+        i1.unshelve();
+        assert_eq!(i1.get_alarm_state(), AlarmState::TrippedUnacked);
+        assert_eq!(
+            InterlockState::from(i1.get_alarm_state()),
+            InterlockState::Active
+        );
+    }
+
+    #[test]
+    /// test that bypassing a latched trip doesn't hide it from the alarm state
+    fn bypass_while_tripped() {
+        let _bypasses = crate::tests::lock_bypasses();
+        let clock = ManualClock::new(0);
+        let i1 = Interlock::with_clock(InterlockableBool::new(false), &clock);
+        i1.set(true);
+        i1.bypass(10, "maintenance");
+        assert_eq!(i1.get_state(), InterlockState::Bypassed);
+        assert_eq!(i1.get_alarm_state(), AlarmState::TrippedUnacked);
+        i1.acknowledge();
+        assert_eq!(i1.get_alarm_state(), AlarmState::TrippedAcked);
+    }
+}
@@ -0,0 +1,242 @@
+//! async waiting on interlock transitions.
+//!
+//! [`AsyncInterlock`] wraps an [`Interlock<T>`] and wakes every registered waker whenever the
+//! interlock (or its inner value) changes, so tasks can `.await` a transition instead of polling
+//! [`Interlock::get_state`] in a loop. it only depends on `core::task`, so it works with any
+//! executor (embassy, rtic, ...).
+
+use core::cell::Cell;
+use core::future::poll_fn;
+use core::task::{Poll, Waker};
+
+use crate::{Error, Interlock, InterlockState, Interlockable};
+
+/// a fixed capacity set of wakers.
+///
+/// registering more than `N` distinct wakers wakes all currently registered ones to make room,
+/// which makes their futures re-poll (and re-register) rather than miss a wake up.
+pub struct WakerSet<const N: usize> {
+    wakers: [Cell<Option<Waker>>; N],
+}
+
+impl<const N: usize> WakerSet<N> {
+    pub const fn new() -> Self {
+        Self {
+            wakers: [const { Cell::new(None) }; N],
+        }
+    }
+
+    /// register a waker to be woken on the next [`WakerSet::wake_all`]
+    pub fn register(&self, waker: &Waker) {
+        let mut free = None;
+        for (i, slot) in self.wakers.iter().enumerate() {
+            match slot.take() {
+                Some(w) if w.will_wake(waker) => {
+                    slot.set(Some(w));
+                    return;
+                }
+                Some(w) => slot.set(Some(w)),
+                None => {
+                    free.get_or_insert(i);
+                }
+            }
+        }
+
+        match free {
+            Some(i) => self.wakers[i].set(Some(waker.clone())),
+            None => {
+                self.wake_all();
+                if let Some(slot) = self.wakers.first() {
+                    slot.set(Some(waker.clone()));
+                } else {
+                    waker.wake_by_ref();
+                }
+            }
+        }
+    }
+
+    /// wake (and unregister) every registered waker
+    pub fn wake_all(&self) {
+        for slot in self.wakers.iter() {
+            if let Some(w) = slot.take() {
+                w.wake();
+            }
+        }
+    }
+}
+
+impl<const N: usize> Default for WakerSet<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// an [`Interlock<T>`] that can be awaited. up to `W` tasks can wait on it at once without
+/// spurious wake ups
+pub struct AsyncInterlock<T: Interlockable, const W: usize = 4> {
+    interlock: Interlock<T>,
+    wakers: WakerSet<W>,
+}
+
+impl<T, const W: usize> AsyncInterlock<T, W>
+where
+    T: Interlockable,
+{
+    pub const fn new(inner: T) -> Self {
+        Self {
+            interlock: Interlock::new(inner),
+            wakers: WakerSet::new(),
+        }
+    }
+
+    /// attempt to clear the interlock. Returns:
+    ///   * Ok(()) if clearing the interlock was successful
+    ///   * Err(Error::ClearError) if clearing the interlock was unsuccessful
+    pub fn try_clear_interlock(&self) -> Result<(), Error> {
+        let r = self.interlock.try_clear_interlock();
+        if r.is_ok() {
+            self.wakers.wake_all();
+        }
+        r
+    }
+
+    /// sets the inner value, and asserts the interlock if the inner value is no longer clear
+    pub fn set(&self, new_value: T::UpdateType) {
+        self.interlock.set(new_value);
+        self.wakers.wake_all();
+    }
+
+    /// clear the inner value with an update type
+    pub fn clear(&self, new_value: T::UpdateType) {
+        self.interlock.clear(new_value);
+        self.wakers.wake_all();
+    }
+
+    /// get the state of the interlock
+    pub fn get_state(&self) -> InterlockState {
+        self.interlock.get_state()
+    }
+
+    /// get a ref of the inner value
+    pub fn get_inner_ref(&self) -> &T {
+        self.interlock.get_inner_ref()
+    }
+
+    /// wait until the interlock is in `state`
+    pub async fn wait_for_state(&self, state: InterlockState) {
+        poll_fn(|cx| {
+            if self.interlock.get_state() == state {
+                Poll::Ready(())
+            } else {
+                self.wakers.register(cx.waker());
+                Poll::Pending
+            }
+        })
+        .await
+    }
+
+    /// wait until the interlock asserts
+    pub async fn wait_until_tripped(&self) {
+        self.wait_for_state(InterlockState::Active).await
+    }
+
+    /// wait until the interlock has been cleared
+    pub async fn wait_until_clear(&self) {
+        self.wait_for_state(InterlockState::Inactive).await
+    }
+
+    /// wait until the inner value is clear, i.e. until [`AsyncInterlock::try_clear_interlock`]
+    /// would succeed
+    pub async fn wait_until_clearable(&self) {
+        poll_fn(|cx| {
+            if self.interlock.get_inner_ref().is_clear() {
+                Poll::Ready(())
+            } else {
+                self.wakers.register(cx.waker());
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
+
+impl<T, const W: usize> AsyncInterlock<T, W>
+where
+    T: Interlockable + Clone,
+{
+    /// get a clone of the inner value
+    pub fn get_inner(&self) -> T {
+        self.interlock.get_inner()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::tests::InterlockableBool;
+    use core::future::Future;
+    use core::pin::pin;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use core::task::Context;
+    use std::sync::Arc;
+    use std::task::Wake;
+
+    struct CountingWaker(AtomicUsize);
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    /// test that waiting for a trip completes once `set` asserts the interlock
+    fn wait_until_tripped() {
+        let i1: AsyncInterlock<_> = AsyncInterlock::new(InterlockableBool::new(false));
+        let counter = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let waker = Waker::from(counter.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let mut fut = pin!(i1.wait_until_tripped());
+        assert_eq!(fut.as_mut().poll(&mut cx), Poll::Pending);
+        i1.set(true);
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+        assert_eq!(fut.as_mut().poll(&mut cx), Poll::Ready(()));
+    }
+
+    #[test]
+    /// test that waiting to be clearable follows the inner value, and that waiting for the
+    /// interlock to clear completes once it is cleared
+    fn wait_until_clearable() {
+        let i1: AsyncInterlock<_> = AsyncInterlock::new(InterlockableBool::new(false));
+        i1.set(true);
+        let waker = Waker::noop();
+        let mut cx = Context::from_waker(waker);
+
+        let mut clearable = pin!(i1.wait_until_clearable());
+        let mut cleared = pin!(i1.wait_until_clear());
+        assert_eq!(clearable.as_mut().poll(&mut cx), Poll::Pending);
+        assert_eq!(cleared.as_mut().poll(&mut cx), Poll::Pending);
+
+        i1.clear(false);
+        assert_eq!(clearable.as_mut().poll(&mut cx), Poll::Ready(()));
+        assert_eq!(cleared.as_mut().poll(&mut cx), Poll::Pending);
+
+        assert_eq!(i1.try_clear_interlock(), Ok(()));
+        assert_eq!(cleared.as_mut().poll(&mut cx), Poll::Ready(()));
+    }
+
+    #[test]
+    /// test that overflowing the waker set wakes the existing wakers instead of dropping them
+    fn waker_set_overflow() {
+        let set: WakerSet<1> = WakerSet::new();
+        let a = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let b = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        set.register(&Waker::from(a.clone()));
+        set.register(&Waker::from(b.clone()));
+        assert_eq!(a.0.load(Ordering::SeqCst), 1);
+        set.wake_all();
+        assert_eq!(b.0.load(Ordering::SeqCst), 1);
+    }
+}
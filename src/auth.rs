@@ -0,0 +1,107 @@
+//! reset authorization.
+//!
+//! an [`Interlock`](crate::Interlock) consults an [`Authorize`] implementation before it clears.
+//! the default, [`Unrestricted`], lets anyone reset the interlock. anything else has to be reset
+//! through [`Interlock::try_clear_interlock_with`](crate::Interlock::try_clear_interlock_with)
+//! with a token the authorizer accepts, e.g. an [`AccessLevel`] for [`RequireLevel`], or a PIN or
+//! key switch reading for a custom authorizer.
+
+/// decides who may reset an interlock
+pub trait Authorize {
+    /// what a caller presents to prove it may reset the interlock
+    type Token: ?Sized;
+
+    /// return true if `token` may reset the interlock
+    fn authorize(&self, token: &Self::Token) -> bool;
+
+    /// return true if the interlock may be reset without a token, through
+    /// [`Interlock::try_clear_interlock`](crate::Interlock::try_clear_interlock)
+    fn allows_anonymous(&self) -> bool {
+        false
+    }
+}
+
+/// anyone may reset the interlock. this is the default
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Unrestricted;
+
+impl Authorize for Unrestricted {
+    type Token = ();
+
+    fn authorize(&self, _token: &Self::Token) -> bool {
+        true
+    }
+
+    fn allows_anonymous(&self) -> bool {
+        true
+    }
+}
+
+/// the role level of whoever is asking for a reset. higher levels may do more
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct AccessLevel(pub u8);
+
+impl AccessLevel {
+    pub const OPERATOR: AccessLevel = AccessLevel(1);
+    pub const SUPERVISOR: AccessLevel = AccessLevel(2);
+    pub const ENGINEER: AccessLevel = AccessLevel(3);
+}
+
+/// only callers at or above a minimum [`AccessLevel`] may reset the interlock
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RequireLevel(pub AccessLevel);
+
+impl Authorize for RequireLevel {
+    type Token = AccessLevel;
+
+    fn authorize(&self, token: &Self::Token) -> bool {
+        *token >= self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::InterlockableBool;
+    use crate::{Error, Interlock, InterlockState};
+
+    #[test]
+    /// test that a supervisor level reset refuses anonymous and operator resets
+    fn require_level() {
+        let i1 = Interlock::new(InterlockableBool::new(false))
+            .with_authorizer(RequireLevel(AccessLevel::SUPERVISOR));
+        i1.set(true);
+        i1.set(false);
+        assert_eq!(i1.try_clear_interlock(), Err(Error::Unauthorized));
+        assert_eq!(
+            i1.try_clear_interlock_with(&AccessLevel::OPERATOR),
+            Err(Error::Unauthorized)
+        );
+        assert_eq!(i1.get_state(), InterlockState::Active);
+        assert_eq!(
+            i1.try_clear_interlock_with(&AccessLevel::SUPERVISOR),
+            Ok(())
+        );
+        assert_eq!(i1.get_state(), InterlockState::Inactive);
+    }
+
+    /// a PIN pad
+    struct Pin(u32);
+    impl Authorize for Pin {
+        type Token = u32;
+        fn authorize(&self, token: &Self::Token) -> bool {
+            *token == self.0
+        }
+    }
+
+    #[test]
+    /// test a custom authorizer, and that an authorized reset still needs the inner value clear
+    fn custom_authorizer() {
+        let i1 = Interlock::new(InterlockableBool::new(false)).with_authorizer(Pin(1234));
+        i1.set(true);
+        assert_eq!(i1.try_clear_interlock_with(&1234), Err(Error::ClearError));
+        i1.set(false);
+        assert_eq!(i1.try_clear_interlock_with(&4321), Err(Error::Unauthorized));
+        assert_eq!(i1.try_clear_interlock_with(&1234), Ok(()));
+    }
+}
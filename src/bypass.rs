@@ -0,0 +1,180 @@
+//! time limited bypasses.
+//!
+//! an [`Interlock`](crate::Interlock) can be bypassed for a fixed duration with
+//! [`Interlock::bypass`](crate::Interlock::bypass). a bypass always expires: there is no way to
+//! bypass an interlock indefinitely, since forgotten bypasses are a hazard of their own. the
+//! number of bypasses active anywhere in the system is available from [`active_bypasses`].
+//!
+//! keeping that count needs atomic read-modify-write, so it only exists on targets with
+//! `target_has_atomic = "ptr"`. on cores without compare-and-swap (e.g. Cortex-M0, thumbv6m)
+//! there is no `active_bypasses`, and bypasses aren't counted. bypasses still expire as usual
+//! there, so keep track of [`Interlock::bypass_reason`](crate::Interlock::bypass_reason) per
+//! interlock instead.
+
+use core::cell::Cell;
+#[cfg(target_has_atomic = "ptr")]
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(target_has_atomic = "ptr")]
+static ACTIVE_BYPASSES: AtomicUsize = AtomicUsize::new(0);
+
+/// get the number of bypasses that are currently active across every interlock. expired
+/// bypasses are only noticed the next time their interlock is used, so poll interlocks (e.g.
+/// with [`Interlock::get_state`](crate::Interlock::get_state)) before relying on the count.
+///
+/// only available on targets with atomic read-modify-write (`target_has_atomic = "ptr"`). cores
+/// without compare-and-swap, like Cortex-M0, don't count bypasses at all
+#[cfg(target_has_atomic = "ptr")]
+pub fn active_bypasses() -> usize {
+    ACTIVE_BYPASSES.load(Ordering::SeqCst)
+}
+
+/// an active bypass
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Bypass<I, D> {
+    pub(crate) since: I,
+    pub(crate) duration: D,
+    pub(crate) reason: &'static str,
+}
+
+/// where an interlock keeps its bypass. ending the bypass (or dropping the slot with a bypass
+/// still in it) is counted in [`active_bypasses`]
+pub(crate) struct Slot<I: Copy, D: Copy> {
+    bypass: Cell<Option<Bypass<I, D>>>,
+}
+
+impl<I: Copy, D: Copy> Slot<I, D> {
+    pub(crate) const fn new() -> Self {
+        Self {
+            bypass: Cell::new(None),
+        }
+    }
+
+    pub(crate) fn get(&self) -> Option<Bypass<I, D>> {
+        self.bypass.get()
+    }
+
+    /// start `bypass`, replacing the current one if any
+    pub(crate) fn replace(&self, bypass: Bypass<I, D>) {
+        if self.bypass.replace(Some(bypass)).is_none() {
+            started();
+        }
+    }
+
+    /// end the current bypass, returning it if there was one
+    pub(crate) fn take(&self) -> Option<Bypass<I, D>> {
+        let bypass = self.bypass.take();
+        if bypass.is_some() {
+            ended();
+        }
+        bypass
+    }
+}
+
+impl<I: Copy, D: Copy> Drop for Slot<I, D> {
+    fn drop(&mut self) {
+        // don't leave a bypass counted that nobody can ever expire
+        self.take();
+    }
+}
+
+/// count a bypass starting. does nothing without atomic read-modify-write, see the module docs
+fn started() {
+    #[cfg(target_has_atomic = "ptr")]
+    ACTIVE_BYPASSES.fetch_add(1, Ordering::SeqCst);
+}
+
+/// count a bypass ending
+fn ended() {
+    #[cfg(target_has_atomic = "ptr")]
+    ACTIVE_BYPASSES.fetch_sub(1, Ordering::SeqCst);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::active_bypasses;
+    use crate::tests::InterlockableBool;
+    use crate::time::{Clock, ManualClock};
+    use crate::{Interlock, InterlockState};
+    use core::cell::Cell;
+
+    /// a free running `u32` tick counter, like a hardware timer
+    struct TickClock(Cell<u32>);
+
+    impl Clock for TickClock {
+        type Instant = u32;
+        fn now(&self) -> Self::Instant {
+            self.0.get()
+        }
+    }
+
+    #[test]
+    /// test that a bypass hides trips, keeps evaluating the inner value, and re-asserts when
+    /// it expires with the inner value still bad
+    fn bypass_expires() {
+        let _bypasses = crate::tests::lock_bypasses();
+        let clock = ManualClock::new(0);
+        let i1 = Interlock::with_clock(InterlockableBool::new(false), &clock);
+        i1.bypass(10, "commissioning");
+        assert_eq!(i1.get_state(), InterlockState::Bypassed);
+        assert_eq!(i1.bypass_reason(), Some("commissioning"));
+
+        i1.set(true);
+        assert_eq!(i1.get_state(), InterlockState::Bypassed);
+        clock.advance(4);
+        assert_eq!(i1.bypass_remaining(), Some(6));
+        clock.advance(6);
+        assert_eq!(i1.get_state(), InterlockState::Active);
+        assert_eq!(i1.bypass_reason(), None);
+    }
+
+    #[test]
+    /// test that a bypass that expires with the inner value clear leaves the interlock inactive,
+    /// and that the system wide count follows bypasses starting and ending
+    fn bypass_count() {
+        let _bypasses = crate::tests::lock_bypasses();
+        let before = active_bypasses();
+        let clock = ManualClock::new(0);
+        let i1 = Interlock::with_clock(InterlockableBool::new(false), &clock);
+        let i2 = Interlock::with_clock(InterlockableBool::new(false), &clock);
+        i1.bypass(10, "a");
+        i2.bypass(20, "b");
+        assert_eq!(active_bypasses(), before + 2);
+        // replacing a bypass doesn't count twice
+        i1.bypass(10, "a again");
+        assert_eq!(active_bypasses(), before + 2);
+
+        // expiry
+        i1.set(true);
+        i1.set(false);
+        clock.advance(10);
+        assert_eq!(i1.get_state(), InterlockState::Inactive);
+        assert_eq!(active_bypasses(), before + 1);
+
+        // removal
+        i2.remove_bypass();
+        assert_eq!(i2.get_state(), InterlockState::Inactive);
+        assert_eq!(active_bypasses(), before);
+
+        // dropping a bypassed interlock
+        let i3 = Interlock::with_clock(InterlockableBool::new(false), &clock);
+        i3.bypass(10, "c");
+        assert_eq!(active_bypasses(), before + 1);
+        drop(i3);
+        assert_eq!(active_bypasses(), before);
+    }
+
+    #[test]
+    /// test that a bypass expires even if the tick counter wraps while it is active
+    fn bypass_expires_across_wrap() {
+        let _bypasses = crate::tests::lock_bypasses();
+        let clock = TickClock(Cell::new(u32::MAX - 5));
+        let i1 = Interlock::with_clock(InterlockableBool::new(true), &clock);
+        i1.bypass(10, "wrap");
+        clock.0.set(2);
+        assert_eq!(i1.bypass_remaining(), Some(2));
+        clock.0.set(4);
+        assert_eq!(i1.get_state(), InterlockState::Active);
+        assert_eq!(i1.bypass_reason(), None);
+    }
+}
@@ -0,0 +1,292 @@
+//! digital I/O bindings.
+//!
+//! [`PinInput`] turns a digital input (a door switch, an e-stop contact, ...) into an
+//! [`Interlockable`], so `Interlock<PinInput<P>>` works out of the box. [`FailSafeOutput`] goes
+//! the other way, and drives an output (a contactor, a solenoid, ...) to a safe level whenever
+//! the interlock trips.
+//!
+//! the [`InputPin`] / [`OutputPin`] traits mirror the `embedded_hal::digital` ones from
+//! embedded-hal 1.0, so a HAL pin is adapted with a newtype that forwards to it.
+
+use core::cell::{Cell, RefCell};
+
+use crate::{Error, InterlockState, Interlockable, Observer};
+
+/// a digital input pin. mirrors `embedded_hal::digital::InputPin`
+pub trait InputPin {
+    type Error;
+    /// is the input pin high?
+    fn is_high(&mut self) -> Result<bool, Self::Error>;
+    /// is the input pin low?
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        self.is_high().map(|high| !high)
+    }
+}
+
+/// a digital output pin. mirrors `embedded_hal::digital::OutputPin`
+pub trait OutputPin {
+    type Error;
+    /// drive the pin low
+    fn set_low(&mut self) -> Result<(), Self::Error>;
+    /// drive the pin high
+    fn set_high(&mut self) -> Result<(), Self::Error>;
+    /// drive the pin to `state`
+    fn set_state(&mut self, state: PinState) -> Result<(), Self::Error> {
+        match state {
+            PinState::Low => self.set_low(),
+            PinState::High => self.set_high(),
+        }
+    }
+}
+
+/// a digital pin level. mirrors `embedded_hal::digital::PinState`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PinState {
+    Low,
+    High,
+}
+
+impl core::ops::Not for PinState {
+    type Output = PinState;
+    fn not(self) -> Self::Output {
+        match self {
+            PinState::Low => PinState::High,
+            PinState::High => PinState::Low,
+        }
+    }
+}
+
+/// which pin level trips the interlock
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Polarity {
+    /// the input is not clear while the pin is high
+    ActiveHigh,
+    /// the input is not clear while the pin is low, e.g. a normally closed contact pulled up
+    ActiveLow,
+}
+
+/// an [`Interlockable`] digital input. the pin is sampled by [`PinInput::refresh`], or by
+/// setting the interlock with `()`. a pin that can't be read is treated as not clear
+pub struct PinInput<P: InputPin> {
+    pin: RefCell<P>,
+    polarity: Polarity,
+    active: Cell<bool>,
+    faulted: Cell<bool>,
+}
+
+impl<P: InputPin> PinInput<P> {
+    /// wrap `pin`. the input starts out clear until it is first sampled
+    pub const fn new(pin: P, polarity: Polarity) -> Self {
+        Self {
+            pin: RefCell::new(pin),
+            polarity,
+            active: Cell::new(false),
+            faulted: Cell::new(false),
+        }
+    }
+
+    /// sample the pin. if the pin can't be read the input is not clear until it can be read
+    /// again
+    pub fn refresh(&self) -> Result<(), P::Error> {
+        let high = self.pin.borrow_mut().is_high();
+        self.faulted.set(high.is_err());
+        let high = high?;
+        self.active.set(match self.polarity {
+            Polarity::ActiveHigh => high,
+            Polarity::ActiveLow => !high,
+        });
+        Ok(())
+    }
+
+    /// return true if the last sample failed
+    pub fn is_faulted(&self) -> bool {
+        self.faulted.get()
+    }
+
+    /// give the pin back
+    pub fn into_inner(self) -> P {
+        self.pin.into_inner()
+    }
+}
+
+impl<P: InputPin> Interlockable for PinInput<P> {
+    /// the update just samples the pin
+    type UpdateType = ();
+
+    fn is_clear(&self) -> bool {
+        !self.active.get() && !self.faulted.get()
+    }
+
+    fn set(&self, _new: Self::UpdateType) {
+        // a read error is recorded in `faulted`, which makes the input not clear
+        let _ = self.refresh();
+    }
+
+    fn clear(&self, _new: Self::UpdateType) {
+        let _ = self.refresh();
+    }
+}
+
+/// an [`Observer`] that owns an output pin, drives it to a safe level when the interlock trips,
+/// and restores it only once the interlock clears. attach it by reference with
+/// [`Interlock::with_observer`](crate::Interlock::with_observer)
+pub struct FailSafeOutput<P: OutputPin> {
+    pin: RefCell<P>,
+    safe: PinState,
+    faulted: Cell<bool>,
+}
+
+impl<P: OutputPin> FailSafeOutput<P> {
+    /// wrap `pin`, which is at `safe` while the interlock is tripped. the pin isn't touched
+    /// until the first transition, or [`FailSafeOutput::sync`]
+    pub const fn new(pin: P, safe: PinState) -> Self {
+        Self {
+            pin: RefCell::new(pin),
+            safe,
+            faulted: Cell::new(false),
+        }
+    }
+
+    /// drive the pin to match `state`, e.g. once at start up. anything but an active interlock
+    /// releases the output
+    pub fn sync(&self, state: InterlockState) -> Result<(), Error> {
+        match state {
+            InterlockState::Active => self.drive(self.safe),
+            _ => self.drive(!self.safe),
+        }
+        self.check()
+    }
+
+    /// returns:
+    ///   * Ok(()) if the pin was driven successfully the last time
+    ///   * Err(Error::Pin) if driving the pin failed, and the output may not be safe
+    pub fn check(&self) -> Result<(), Error> {
+        match self.faulted.get() {
+            true => Err(Error::Pin),
+            false => Ok(()),
+        }
+    }
+
+    /// give the pin back
+    pub fn into_inner(self) -> P {
+        self.pin.into_inner()
+    }
+
+    fn drive(&self, level: PinState) {
+        let r = self.pin.borrow_mut().set_state(level);
+        self.faulted.set(r.is_err());
+    }
+}
+
+impl<U, P: OutputPin> Observer<U> for FailSafeOutput<P> {
+    fn on_trip(&self) {
+        self.drive(self.safe);
+    }
+
+    fn on_clear(&self) {
+        self.drive(!self.safe);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::InterlockableBool;
+    use crate::Interlock;
+
+    /// a pin that plays back a fixed sequence of samples, `None` being a read error
+    struct MockPin<const N: usize> {
+        samples: [Option<bool>; N],
+        next: usize,
+    }
+
+    impl<const N: usize> MockPin<N> {
+        fn new(samples: [Option<bool>; N]) -> Self {
+            Self { samples, next: 0 }
+        }
+    }
+
+    impl<const N: usize> InputPin for MockPin<N> {
+        type Error = ();
+        fn is_high(&mut self) -> Result<bool, Self::Error> {
+            let sample = self.samples[self.next];
+            self.next += 1;
+            sample.ok_or(())
+        }
+    }
+
+    #[test]
+    /// test an active low e-stop contact tripping an interlock
+    fn active_low() {
+        let pin = MockPin::new([Some(true), Some(false), Some(true)]);
+        let i1 = Interlock::new(PinInput::new(pin, Polarity::ActiveLow));
+        i1.set(());
+        assert_eq!(i1.get_state(), InterlockState::Inactive);
+        i1.set(());
+        assert_eq!(i1.get_state(), InterlockState::Active);
+        i1.set(());
+        assert_eq!(i1.try_clear_interlock(), Ok(()));
+    }
+
+    /// an output that remembers its level, and fails every write while `broken`
+    struct MockOutput {
+        level: Option<PinState>,
+        broken: bool,
+    }
+
+    impl OutputPin for MockOutput {
+        type Error = ();
+        fn set_low(&mut self) -> Result<(), Self::Error> {
+            self.set_state(PinState::Low)
+        }
+        fn set_high(&mut self) -> Result<(), Self::Error> {
+            self.set_state(PinState::High)
+        }
+        fn set_state(&mut self, state: PinState) -> Result<(), Self::Error> {
+            match self.broken {
+                true => Err(()),
+                false => {
+                    self.level = Some(state);
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    #[test]
+    /// test that the output is de-energized on a trip, and only restored after a clear
+    fn fail_safe_output() {
+        let output = FailSafeOutput::new(
+            MockOutput {
+                level: None,
+                broken: false,
+            },
+            PinState::Low,
+        );
+        let i1 = Interlock::new(InterlockableBool::new(false)).with_observer(&output);
+        assert_eq!(output.sync(i1.get_state()), Ok(()));
+        assert_eq!(output.pin.borrow().level, Some(PinState::High));
+
+        i1.set(true);
+        assert_eq!(output.pin.borrow().level, Some(PinState::Low));
+        i1.set(false);
+        assert_eq!(output.pin.borrow().level, Some(PinState::Low));
+        i1.try_clear_interlock().unwrap();
+        assert_eq!(output.pin.borrow().level, Some(PinState::High));
+
+        output.pin.borrow_mut().broken = true;
+        i1.set(true);
+        assert_eq!(output.check(), Err(Error::Pin));
+    }
+
+    #[test]
+    /// test that a pin read error fails safe
+    fn read_error() {
+        let input = PinInput::new(MockPin::new([None, Some(false)]), Polarity::ActiveHigh);
+        assert_eq!(input.refresh(), Err(()));
+        assert!(input.is_faulted());
+        assert!(!input.is_clear());
+        assert_eq!(input.refresh(), Ok(()));
+        assert!(input.is_clear());
+    }
+}
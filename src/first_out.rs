@@ -0,0 +1,120 @@
+//! first-out annunciation.
+//!
+//! when one input trips, the rest of the process usually follows a moment later and every input
+//! ends up bad. [`FirstOutGroup`] latches the index of the input that went non-clear first, and
+//! holds on to it until the group is reset, so the actual cause of a cascading trip can still be
+//! diagnosed.
+
+use core::cell::Cell;
+
+use crate::{Error, InterlockState, Interlockable};
+
+/// an object safe view of an [`Interlockable`], so inputs with different update types can be
+/// grouped together. implemented for every [`Interlockable`]
+pub trait Condition {
+    /// return true if the input is in a state that allows clearing the interlock
+    fn is_input_clear(&self) -> bool;
+}
+
+impl<T: Interlockable> Condition for T {
+    fn is_input_clear(&self) -> bool {
+        self.is_clear()
+    }
+}
+
+/// a latched group of inputs that remembers which input tripped first
+pub struct FirstOutGroup<'a, const N: usize> {
+    inputs: [&'a dyn Condition; N],
+    first_out: Cell<Option<usize>>,
+}
+
+impl<'a, const N: usize> FirstOutGroup<'a, N> {
+    pub const fn new(inputs: [&'a dyn Condition; N]) -> Self {
+        Self {
+            inputs,
+            first_out: Cell::new(None),
+        }
+    }
+
+    /// scan the inputs, and latch the first one that is not clear. if several inputs went bad
+    /// since the last scan, the lowest index wins, so scan at least as fast as the inputs change.
+    /// returns the state of the group after the scan
+    pub fn scan(&self) -> InterlockState {
+        if self.first_out.get().is_none() {
+            let first = self.inputs.iter().position(|input| !input.is_input_clear());
+            self.first_out.set(first);
+        }
+        self.get_state()
+    }
+
+    /// get the index of the input that tripped the group, if it is tripped
+    pub fn first_out(&self) -> Option<usize> {
+        self.first_out.get()
+    }
+
+    /// get the state of the group
+    pub fn get_state(&self) -> InterlockState {
+        match self.first_out.get() {
+            Some(_) => InterlockState::Active,
+            None => InterlockState::Inactive,
+        }
+    }
+
+    /// attempt to reset the group, forgetting the first-out. Returns:
+    ///   * Ok(()) if every input is clear
+    ///   * Err(Error::ClearError) if any input is still not clear
+    pub fn try_reset(&self) -> Result<(), Error> {
+        match self.inputs.iter().all(|input| input.is_input_clear()) {
+            true => {
+                self.first_out.set(None);
+                Ok(())
+            }
+            false => Err(Error::ClearError),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::InterlockableBool;
+
+    #[test]
+    /// test that the first input to trip is latched, even after others follow
+    fn cascading_trip() {
+        let inputs = [
+            InterlockableBool::new(false),
+            InterlockableBool::new(false),
+            InterlockableBool::new(false),
+        ];
+        let group = FirstOutGroup::new([&inputs[0], &inputs[1], &inputs[2]]);
+        assert_eq!(group.scan(), InterlockState::Inactive);
+        assert_eq!(group.first_out(), None);
+
+        inputs[2].set(true);
+        assert_eq!(group.scan(), InterlockState::Active);
+        inputs[0].set(true);
+        inputs[1].set(true);
+        group.scan();
+        assert_eq!(group.first_out(), Some(2));
+    }
+
+    #[test]
+    /// test that the group can only be reset once every input is clear
+    fn reset() {
+        let inputs = [InterlockableBool::new(true), InterlockableBool::new(false)];
+        let group = FirstOutGroup::new([&inputs[0], &inputs[1]]);
+        group.scan();
+        assert_eq!(group.first_out(), Some(0));
+
+        inputs[0].set(false);
+        inputs[1].set(true);
+        assert_eq!(group.try_reset(), Err(Error::ClearError));
+        assert_eq!(group.first_out(), Some(0));
+
+        inputs[1].set(false);
+        assert_eq!(group.try_reset(), Ok(()));
+        assert_eq!(group.get_state(), InterlockState::Inactive);
+        assert_eq!(group.first_out(), None);
+    }
+}
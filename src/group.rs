@@ -0,0 +1,213 @@
+//! groups of heterogeneous interlocks.
+//!
+//! [`InterlockGroup`] combines a fixed number of interlocks (of any inner type, and any of the
+//! interlock flavours in this crate) through the object safe [`DynInterlock`] trait, and reports
+//! an aggregate state using either "any tripped" (OR) or "all tripped" (AND) logic.
+
+use crate::{
+    AsyncInterlock, Authorize, Clock, CriticalSection, Error, IndexSet, Interlock, InterlockState,
+    Interlockable, MutexInterlock, Observer, SyncInterlock,
+};
+
+/// an object safe view of an interlock, so interlocks with different inner types can be grouped
+pub trait DynInterlock {
+    /// get the state of the interlock
+    fn get_state(&self) -> InterlockState;
+    /// attempt to clear the interlock
+    fn try_clear_interlock(&self) -> Result<(), Error>;
+}
+
+impl<T, C, A, O> DynInterlock for Interlock<T, C, A, O>
+where
+    T: Interlockable,
+    C: Clock,
+    A: Authorize,
+    O: Observer<T::UpdateType>,
+{
+    fn get_state(&self) -> InterlockState {
+        Interlock::get_state(self)
+    }
+    fn try_clear_interlock(&self) -> Result<(), Error> {
+        Interlock::try_clear_interlock(self).map_err(Error::erase_duration)
+    }
+}
+
+impl<T: Interlockable> DynInterlock for SyncInterlock<T> {
+    fn get_state(&self) -> InterlockState {
+        SyncInterlock::get_state(self)
+    }
+    fn try_clear_interlock(&self) -> Result<(), Error> {
+        SyncInterlock::try_clear_interlock(self)
+    }
+}
+
+impl<CS: CriticalSection, T: Interlockable> DynInterlock for MutexInterlock<CS, T> {
+    fn get_state(&self) -> InterlockState {
+        MutexInterlock::get_state(self)
+    }
+    fn try_clear_interlock(&self) -> Result<(), Error> {
+        MutexInterlock::try_clear_interlock(self)
+    }
+}
+
+impl<T: Interlockable, const W: usize> DynInterlock for AsyncInterlock<T, W> {
+    fn get_state(&self) -> InterlockState {
+        AsyncInterlock::get_state(self)
+    }
+    fn try_clear_interlock(&self) -> Result<(), Error> {
+        AsyncInterlock::try_clear_interlock(self)
+    }
+}
+
+/// how the members of a group combine into the state of the group
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GroupLogic {
+    /// the group is active if any member is active
+    Any,
+    /// the group is active only if every member is active
+    All,
+}
+
+/// a fixed capacity group of interlocks
+pub struct InterlockGroup<'a, const N: usize> {
+    logic: GroupLogic,
+    members: [&'a dyn DynInterlock; N],
+}
+
+impl<'a, const N: usize> InterlockGroup<'a, N> {
+    pub const fn new(logic: GroupLogic, members: [&'a dyn DynInterlock; N]) -> Self {
+        Self { logic, members }
+    }
+
+    /// return true if any member is active
+    pub fn any_tripped(&self) -> bool {
+        self.members
+            .iter()
+            .any(|m| m.get_state() == InterlockState::Active)
+    }
+
+    /// return true if every member is active. an empty group is never tripped
+    pub fn all_tripped(&self) -> bool {
+        N > 0
+            && self
+                .members
+                .iter()
+                .all(|m| m.get_state() == InterlockState::Active)
+    }
+
+    /// get the set of members that are active
+    pub fn tripped(&self) -> IndexSet<N> {
+        self.members
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| m.get_state() == InterlockState::Active)
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// get the state of the group, according to its [`GroupLogic`]
+    pub fn get_state(&self) -> InterlockState {
+        let tripped = match self.logic {
+            GroupLogic::Any => self.any_tripped(),
+            GroupLogic::All => self.all_tripped(),
+        };
+        match tripped {
+            true => InterlockState::Active,
+            false => InterlockState::Inactive,
+        }
+    }
+
+    /// attempt to clear every member. members that can be cleared are cleared even if others
+    /// refuse. Returns:
+    ///   * Ok(()) if every member cleared
+    ///   * Err(refused) with the set of members that refused to clear
+    pub fn try_clear_all(&self) -> Result<(), IndexSet<N>> {
+        let refused: IndexSet<N> = self
+            .members
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| m.try_clear_interlock().is_err())
+            .map(|(i, _)| i)
+            .collect();
+        match refused.is_empty() {
+            true => Ok(()),
+            false => Err(refused),
+        }
+    }
+}
+
+impl<const N: usize> DynInterlock for InterlockGroup<'_, N> {
+    fn get_state(&self) -> InterlockState {
+        InterlockGroup::get_state(self)
+    }
+    fn try_clear_interlock(&self) -> Result<(), Error> {
+        self.try_clear_all().map_err(|_| Error::ClearError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::InterlockableBool;
+    use core::cell::Cell;
+
+    /// a second inner type, to check that groups really are heterogeneous
+    #[derive(Clone)]
+    struct OverLimit {
+        val: Cell<u8>,
+    }
+
+    impl Interlockable for OverLimit {
+        type UpdateType = u8;
+        fn is_clear(&self) -> bool {
+            self.val.get() <= 100
+        }
+
+        fn set(&self, new: Self::UpdateType) {
+            self.val.set(new);
+        }
+
+        fn clear(&self, new: Self::UpdateType) {
+            self.val.set(new);
+        }
+    }
+
+    #[test]
+    /// test the any / all aggregate states
+    fn aggregate_state() {
+        let i1 = Interlock::new(InterlockableBool::new(false));
+        let i2 = Interlock::new(OverLimit { val: Cell::new(0) });
+        let any = InterlockGroup::new(GroupLogic::Any, [&i1, &i2]);
+        let all = InterlockGroup::new(GroupLogic::All, [&i1, &i2]);
+        assert_eq!(any.get_state(), InterlockState::Inactive);
+
+        i1.set(true);
+        assert_eq!(any.get_state(), InterlockState::Active);
+        assert_eq!(all.get_state(), InterlockState::Inactive);
+        assert!(any.tripped().contains(0));
+        assert!(!any.tripped().contains(1));
+
+        i2.set(200);
+        assert_eq!(all.get_state(), InterlockState::Active);
+    }
+
+    #[test]
+    /// test that clearing a group clears what it can and reports the members that refused
+    fn try_clear_all() {
+        let i1 = Interlock::new(InterlockableBool::new(false));
+        let i2 = Interlock::new(OverLimit { val: Cell::new(0) });
+        let group = InterlockGroup::new(GroupLogic::Any, [&i1, &i2]);
+        i1.set(true);
+        i2.set(200);
+        i1.set(false);
+
+        let refused = group.try_clear_all().unwrap_err();
+        assert!(refused.contains(1));
+        assert_eq!(refused.len(), 1);
+        assert_eq!(i1.get_state(), InterlockState::Inactive);
+
+        i2.set(50);
+        assert_eq!(group.try_clear_all(), Ok(()));
+        assert_eq!(group.get_state(), InterlockState::Inactive);
+    }
+}
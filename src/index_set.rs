@@ -0,0 +1,70 @@
+//! a fixed capacity set of indices, used to report which members of a group or which channels of
+//! a vote something applies to without allocating.
+
+/// a set of indices in `0..N`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IndexSet<const N: usize> {
+    members: [bool; N],
+}
+
+impl<const N: usize> IndexSet<N> {
+    /// an empty set
+    pub const fn new() -> Self {
+        Self {
+            members: [false; N],
+        }
+    }
+
+    /// add `index` to the set. indices outside of `0..N` are ignored
+    pub fn insert(&mut self, index: usize) {
+        if let Some(m) = self.members.get_mut(index) {
+            *m = true;
+        }
+    }
+
+    /// remove `index` from the set
+    pub fn remove(&mut self, index: usize) {
+        if let Some(m) = self.members.get_mut(index) {
+            *m = false;
+        }
+    }
+
+    /// return true if `index` is in the set
+    pub fn contains(&self, index: usize) -> bool {
+        self.members.get(index).copied().unwrap_or(false)
+    }
+
+    /// the number of indices in the set
+    pub fn len(&self) -> usize {
+        self.members.iter().filter(|m| **m).count()
+    }
+
+    /// return true if the set is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// iterate over the indices in the set, in ascending order
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.members
+            .iter()
+            .enumerate()
+            .filter_map(|(i, m)| m.then_some(i))
+    }
+}
+
+impl<const N: usize> Default for IndexSet<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> FromIterator<usize> for IndexSet<N> {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = Self::new();
+        for i in iter {
+            set.insert(i);
+        }
+        set
+    }
+}
@@ -1,10 +1,55 @@
 #![no_std]
+#[cfg(feature = "std")]
+extern crate std;
+// lets the code generated by `#[derive(Interlockable)]` be tested in this crate
+#[cfg(test)]
+extern crate self as interlock_rs;
+
 use thiserror_no_std::Error;
 
 use core::cell::Cell;
 
+mod alarm;
+mod asynch;
+mod auth;
+mod bypass;
+#[cfg(feature = "digital")]
+pub mod digital;
+mod first_out;
+mod group;
+mod index_set;
+pub mod log;
+mod matrix;
+mod mutex;
+mod observer;
+pub mod persist;
+pub mod quality;
+pub mod rate;
+mod sync;
+pub mod threshold;
+pub mod time;
+mod voting;
+pub mod watchdog;
+pub use alarm::AlarmState;
+pub use asynch::{AsyncInterlock, WakerSet};
+pub use auth::{AccessLevel, Authorize, RequireLevel, Unrestricted};
+#[cfg(target_has_atomic = "ptr")]
+pub use bypass::active_bypasses;
+pub use first_out::{Condition, FirstOutGroup};
+pub use group::{DynInterlock, GroupLogic, InterlockGroup};
+pub use index_set::IndexSet;
+#[cfg(feature = "derive")]
+pub use interlock_rs_derive::Interlockable;
+pub use mutex::{CriticalSection, MutexInterlock};
+pub use observer::{NoObserver, Observer};
+pub use sync::SyncInterlock;
+pub use time::{Clock, ClockDuration, NoClock};
+use time::{Duration, Instant};
+pub use voting::{DegradedMode, Voting};
+
 /// the interlockable trait defines the behavior that the inner type T of the [`Interlock<T>`]
-/// is required to implement.
+/// is required to implement. with the `derive` feature, it can be derived for structs of `Cell`s,
+/// see `interlock-rs-derive` for the attributes.
 pub trait Interlockable {
     type UpdateType;
     /// return true if T is in a state that allows clearing the interlock, false otherwise
@@ -13,102 +58,679 @@ pub trait Interlockable {
     fn clear(&self, new: Self::UpdateType);
 }
 
-/// interlock crate errors
-#[derive(Error, Debug, PartialEq)]
-pub enum Error {
+/// interlock crate errors. D is the [`Duration`](time::Duration) type of the interlock's
+/// [`Clock`], which is `()` without one
+#[derive(Error, Debug, Clone, Copy, PartialEq)]
+pub enum Error<D = ()> {
     #[error("Failed to clear interlock")]
     ClearError,
+    #[error("Interlock reset held off, available in {remaining:?}")]
+    HoldOff { remaining: D },
+    #[error("Not authorized to clear interlock")]
+    Unauthorized,
+    #[error("Failed to drive output pin")]
+    Pin,
+}
+
+impl<D> Error<D> {
+    /// drop the duration carried by the error, for callers that don't know the clock type
+    pub fn erase_duration(self) -> Error {
+        match self {
+            Error::ClearError => Error::ClearError,
+            Error::HoldOff { .. } => Error::HoldOff { remaining: () },
+            Error::Unauthorized => Error::Unauthorized,
+            Error::Pin => Error::Pin,
+        }
+    }
 }
 
-/// the interlock state. pretty much what it says on the tin - either active or inactive
+/// the interlock state. pretty much what it says on the tin - either active or inactive, or
+/// pending while a [`TripDelay`] runs out, or bypassed
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum InterlockState {
     Inactive,
     Active,
+    /// the inner value is not clear, but hasn't been for long enough to assert the interlock
+    Pending,
+    /// the interlock is temporarily bypassed, see [`Interlock::bypass`]
+    Bypassed,
+}
+
+impl InterlockState {
+    /// decode a state stored as a `u8` (see [`SyncInterlock`]). unknown values decode as
+    /// [`InterlockState::Active`], so a corrupted state fails safe
+    pub(crate) const fn from_u8(value: u8) -> Self {
+        match value {
+            0 => InterlockState::Inactive,
+            2 => InterlockState::Pending,
+            3 => InterlockState::Bypassed,
+            _ => InterlockState::Active,
+        }
+    }
 }
 
 impl From<InterlockState> for bool {
     fn from(value: InterlockState) -> Self {
         match value {
             InterlockState::Active => true,
-            InterlockState::Inactive => false,
+            InterlockState::Inactive | InterlockState::Pending | InterlockState::Bypassed => false,
         }
     }
 }
 
-/// The interlock struct. Owns a type T which is the underlying value we interlock off of
-pub struct Interlock<T: Interlockable + Clone> {
+/// how long the inner value has to stay not clear before the interlock asserts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TripDelay<D> {
+    /// assert as soon as the inner value is not clear
+    None,
+    /// assert once this many consecutive [`Interlock::set`] calls left the inner value not clear
+    Samples(u32),
+    /// assert once the inner value has been not clear for this long
+    Duration(D),
+}
+
+/// whether an active interlock waits for [`Interlock::try_clear_interlock`], or follows the inner
+/// value back to inactive by itself
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LatchMode<D> {
+    /// stay active until explicitly cleared
+    Latched,
+    /// go inactive as soon as the inner value is clear again
+    AutoReset,
+    /// go inactive once the inner value has been continuously clear for this long
+    AutoResetAfter(D),
+}
+
+/// The interlock struct. Owns a type T which is the underlying value we interlock off of,
+/// optionally a [`Clock`] C that time based behaviour is measured with, an [`Authorize`] A that
+/// decides who may reset it, and an [`Observer`] O that is told about every transition
+pub struct Interlock<
+    T: Interlockable,
+    C: Clock = NoClock,
+    A: Authorize = Unrestricted,
+    O: Observer<T::UpdateType> = NoObserver,
+> {
     inner: T,
+    authorizer: A,
+    observer: O,
     state: Cell<InterlockState>,
+    clock: C,
+    tripped_at: Cell<Option<C::Instant>>,
+    trip_delay: TripDelay<ClockDuration<C>>,
+    pending_since: Cell<Option<C::Instant>>,
+    pending_samples: Cell<u32>,
+    reset_holdoff: Option<ClockDuration<C>>,
+    clear_since: Cell<Option<C::Instant>>,
+    latch_mode: LatchMode<ClockDuration<C>>,
+    acked: Cell<bool>,
+    shelved: Cell<bool>,
+    suppressed: Cell<bool>,
+    bypass: bypass::Slot<C::Instant, ClockDuration<C>>,
 }
 
 impl<T> Interlock<T>
 where
-    T: Interlockable + Clone,
+    T: Interlockable,
 {
     pub const fn new(inner: T) -> Self {
+        Self::with_clock(inner, NoClock)
+    }
+}
+
+impl<T, C> Interlock<T, C>
+where
+    T: Interlockable,
+    C: Clock,
+{
+    /// create an interlock that measures time with `clock`
+    pub const fn with_clock(inner: T, clock: C) -> Self {
         Self {
             inner,
+            authorizer: Unrestricted,
+            observer: NoObserver,
             state: Cell::new(InterlockState::Inactive),
+            clock,
+            tripped_at: Cell::new(None),
+            trip_delay: TripDelay::None,
+            pending_since: Cell::new(None),
+            pending_samples: Cell::new(0),
+            reset_holdoff: None,
+            clear_since: Cell::new(None),
+            latch_mode: LatchMode::Latched,
+            acked: Cell::new(true),
+            shelved: Cell::new(false),
+            suppressed: Cell::new(false),
+            bypass: bypass::Slot::new(),
         }
     }
+}
+
+impl<T, C, A, O> Interlock<T, C, A, O>
+where
+    T: Interlockable,
+    C: Clock,
+    A: Authorize,
+    O: Observer<T::UpdateType>,
+{
+    /// only allow resets that `authorizer` accepts. see [`Interlock::try_clear_interlock_with`]
+    pub fn with_authorizer<A2: Authorize>(self, authorizer: A2) -> Interlock<T, C, A2, O> {
+        let Interlock {
+            inner,
+            authorizer: _,
+            observer,
+            state,
+            clock,
+            tripped_at,
+            trip_delay,
+            pending_since,
+            pending_samples,
+            reset_holdoff,
+            clear_since,
+            latch_mode,
+            acked,
+            shelved,
+            suppressed,
+            bypass,
+        } = self;
+        Interlock {
+            inner,
+            authorizer,
+            observer,
+            state,
+            clock,
+            tripped_at,
+            trip_delay,
+            pending_since,
+            pending_samples,
+            reset_holdoff,
+            clear_since,
+            latch_mode,
+            acked,
+            shelved,
+            suppressed,
+            bypass,
+        }
+    }
+
+    /// report every transition of the interlock to `observer`
+    pub fn with_observer<O2: Observer<T::UpdateType>>(
+        self,
+        observer: O2,
+    ) -> Interlock<T, C, A, O2> {
+        let Interlock {
+            observer: _,
+            inner,
+            authorizer,
+            state,
+            clock,
+            tripped_at,
+            trip_delay,
+            pending_since,
+            pending_samples,
+            reset_holdoff,
+            clear_since,
+            latch_mode,
+            acked,
+            shelved,
+            suppressed,
+            bypass,
+        } = self;
+        Interlock {
+            observer,
+            inner,
+            authorizer,
+            state,
+            clock,
+            tripped_at,
+            trip_delay,
+            pending_since,
+            pending_samples,
+            reset_holdoff,
+            clear_since,
+            latch_mode,
+            acked,
+            shelved,
+            suppressed,
+            bypass,
+        }
+    }
+
+    /// restore a latched state saved before a reboot, see [`persist`]. only
+    /// [`InterlockState::Active`] is restored, as an unacknowledged trip. everything else starts
+    /// inactive and is re-evaluated on the next [`Interlock::set`]. [`Interlock::tripped_at`]
+    /// is `None` for a restored trip, since an instant from before the reboot means nothing to
+    /// the clock now. the persistent timestamp is in [`persist::FlashStore::recent_trips`]
+    pub fn with_restored_state(self, state: InterlockState) -> Self {
+        if state == InterlockState::Active {
+            self.state.set(InterlockState::Active);
+            self.acked.set(false);
+        }
+        self
+    }
+
+    /// choose whether the interlock latches (the default) or resets by itself once the inner
+    /// value is clear, e.g. for permissives that should just follow their input
+    pub fn with_latch_mode(mut self, mode: LatchMode<ClockDuration<C>>) -> Self {
+        self.latch_mode = mode;
+        self
+    }
+
+    /// require the inner value to stay not clear for `delay` before the interlock asserts, so
+    /// transient glitches don't latch. while the delay runs the interlock is
+    /// [`InterlockState::Pending`]
+    pub fn with_trip_delay(mut self, delay: TripDelay<ClockDuration<C>>) -> Self {
+        self.trip_delay = delay;
+        self
+    }
+
+    /// require the inner value to have been continuously clear for `holdoff` before an active
+    /// interlock can be cleared
+    pub fn with_reset_holdoff(mut self, holdoff: ClockDuration<C>) -> Self {
+        self.reset_holdoff = Some(holdoff);
+        self
+    }
 
     /// attempt to clear the interlock. Returns:
     ///   * Ok(()) if clearing the interlock was successful
     ///   * Err(Error::ClearError) if clearing the interlock was unsuccessful
-    pub fn try_clear_interlock(&self) -> Result<(), Error> {
-        match self.inner.is_clear() {
-            true => {
-                self.state.replace(InterlockState::Inactive);
-                Ok(())
+    ///   * Err(Error::HoldOff { remaining }) if the inner value hasn't been clear for the reset
+    ///     hold-off yet
+    ///   * Err(Error::Unauthorized) if the interlock's [`Authorize`] doesn't allow resets
+    ///     without a token
+    pub fn try_clear_interlock(&self) -> Result<(), Error<ClockDuration<C>>> {
+        let r = match self.authorizer.allows_anonymous() {
+            true => self.clear_interlock(),
+            false => Err(Error::Unauthorized),
+        };
+        self.report_refusal(r)
+    }
+
+    /// attempt to clear the interlock, presenting `token` to the interlock's [`Authorize`].
+    /// Returns the same as [`Interlock::try_clear_interlock`], with Err(Error::Unauthorized) if
+    /// `token` is refused
+    pub fn try_clear_interlock_with(
+        &self,
+        token: &A::Token,
+    ) -> Result<(), Error<ClockDuration<C>>> {
+        let r = match self.authorizer.authorize(token) {
+            true => self.clear_interlock(),
+            false => Err(Error::Unauthorized),
+        };
+        self.report_refusal(r)
+    }
+
+    /// tell the observer about a refused clear
+    fn report_refusal(
+        &self,
+        r: Result<(), Error<ClockDuration<C>>>,
+    ) -> Result<(), Error<ClockDuration<C>>> {
+        if let Err(e) = r {
+            self.observer.on_clear_refused(e.erase_duration());
+        }
+        r
+    }
+
+    /// clear the interlock, once authorization has been checked
+    fn clear_interlock(&self) -> Result<(), Error<ClockDuration<C>>> {
+        let now = self.clock.now();
+        self.update_bypass(now);
+        self.track_clear(now);
+        if !self.inner.is_clear() {
+            return Err(Error::ClearError);
+        }
+
+        if self.state.get() == InterlockState::Active {
+            if let (Some(holdoff), Some(since)) = (self.reset_holdoff, self.clear_since.get()) {
+                let elapsed = now.saturating_duration_since(since);
+                if elapsed < holdoff {
+                    return Err(Error::HoldOff {
+                        remaining: holdoff.saturating_sub(elapsed),
+                    });
+                }
             }
-            false => Err(Error::ClearError),
         }
+
+        self.reset();
+        Ok(())
     }
 
     /// sets the inner value, and asserts the interlock if the inner value is no longer clear
+    /// (once the [`TripDelay`], if any, has run out)
     pub fn set(&self, new_value: T::UpdateType) {
+        self.observer.on_update(&new_value);
         self.inner.set(new_value);
+        self.evaluate(true);
+    }
+
+    /// re-evaluate the inner value without updating it, like [`Interlock::set`] does after
+    /// updating it. this is for inner types that can stop being clear by themselves, like a
+    /// [`Watchdog`](watchdog::Watchdog) timing out, so call it periodically for those. a poll
+    /// isn't a new sample, so it doesn't count towards [`TripDelay::Samples`]
+    pub fn poll(&self) {
+        self.evaluate(false);
+    }
+
+    /// trip (or start the trip delay) if the inner value isn't clear. `sample` is true if the
+    /// inner value was just updated, which counts towards [`TripDelay::Samples`]
+    fn evaluate(&self, sample: bool) {
+        let now = self.clock.now();
+        self.update_bypass(now);
+        self.track_clear(now);
+
+        if self.state.get() == InterlockState::Active {
+            self.update_auto_reset(now);
+            return;
+        }
+        // a bypassed interlock doesn't trip. the inner value is re-checked when the bypass ends
+        if self.bypass.get().is_some() {
+            return;
+        }
+        if self.inner.is_clear() {
+            self.cancel_pending();
+            return;
+        }
 
         // if we aren't in an active interlock state, and we
-        // aren't clear anymore, assert the interlock
-        if (!self.inner.is_clear()) && (self.state.get() == InterlockState::Inactive) {
-            self.state.set(InterlockState::Active);
+        // aren't clear anymore, assert the interlock (or start the trip delay)
+        if sample {
+            self.pending_samples
+                .set(self.pending_samples.get().saturating_add(1));
+        }
+        if self.pending_since.get().is_none() {
+            self.pending_since.set(Some(now));
         }
+        self.update_pending();
     }
 
     /// clear the inner value with an update type
     pub fn clear(&self, new_value: T::UpdateType) {
+        self.observer.on_update(&new_value);
         self.inner.clear(new_value);
+        let now = self.clock.now();
+        self.track_clear(now);
+        match self.state.get() {
+            InterlockState::Pending if self.inner.is_clear() => self.cancel_pending(),
+            InterlockState::Active => self.update_auto_reset(now),
+            _ => {}
+        }
     }
 
-    /// get the state of the interlock
+    /// get the state of the interlock. time based transitions (a [`TripDelay::Duration`] or a
+    /// [`LatchMode::AutoResetAfter`] running out) are applied here, so they happen even if
+    /// nothing calls [`Interlock::set`]
     pub fn get_state(&self) -> InterlockState {
+        let now = self.clock.now();
+        self.update_bypass(now);
+        if self.bypass.get().is_some() {
+            return InterlockState::Bypassed;
+        }
+        match self.state.get() {
+            InterlockState::Pending => self.update_pending(),
+            InterlockState::Active => {
+                self.track_clear(now);
+                self.update_auto_reset(now);
+            }
+            InterlockState::Inactive | InterlockState::Bypassed => {}
+        }
         self.state.get()
     }
 
-    /// get a clone of the inner value
-    pub fn get_inner(&self) -> T {
-        self.inner.clone()
+    /// bypass the interlock for `duration`. while bypassed, [`Interlock::get_state`] reports
+    /// [`InterlockState::Bypassed`] and the interlock doesn't trip. when the bypass expires (or
+    /// is removed) the interlock asserts straight away if the inner value is still not clear.
+    /// bypassing an already bypassed interlock replaces the bypass
+    pub fn bypass(&self, duration: ClockDuration<C>, reason: &'static str) {
+        self.bypass.replace(bypass::Bypass {
+            since: self.clock.now(),
+            duration,
+            reason,
+        });
+        self.observer.on_bypass(reason);
+        // the bypass takes over from a running trip delay
+        self.pending_since.set(None);
+        self.pending_samples.set(0);
+        if self.state.get() == InterlockState::Pending {
+            self.state.set(InterlockState::Inactive);
+        }
+    }
+
+    /// end a bypass early
+    pub fn remove_bypass(&self) {
+        self.end_bypass(self.clock.now());
+    }
+
+    /// get the reason given for the active bypass, if any
+    pub fn bypass_reason(&self) -> Option<&'static str> {
+        self.update_bypass(self.clock.now());
+        self.bypass.get().map(|b| b.reason)
+    }
+
+    /// get the time left on the active bypass, if any
+    pub fn bypass_remaining(&self) -> Option<ClockDuration<C>> {
+        let now = self.clock.now();
+        self.update_bypass(now);
+        self.bypass.get().map(|b| {
+            b.duration
+                .saturating_sub(now.saturating_duration_since(b.since))
+        })
+    }
+
+    /// end the bypass if it has expired
+    fn update_bypass(&self, now: C::Instant) {
+        if let Some(b) = self.bypass.get() {
+            if now.saturating_duration_since(b.since) >= b.duration {
+                self.end_bypass(now);
+            }
+        }
+    }
+
+    /// end the bypass, and re-assert the interlock if the inner value is still not clear
+    fn end_bypass(&self, now: C::Instant) {
+        if self.bypass.take().is_none() {
+            return;
+        }
+        self.observer.on_bypass_end();
+        if !self.inner.is_clear() && self.state.get() != InterlockState::Active {
+            self.trip(now);
+        }
+    }
+
+    /// assert the interlock if the trip delay has run out, otherwise mark it pending
+    fn update_pending(&self) {
+        // the inner value may have gone clear without going through `set` or `clear`
+        if self.inner.is_clear() {
+            self.cancel_pending();
+            return;
+        }
+        let now = self.clock.now();
+        let expired = match self.trip_delay {
+            TripDelay::None => true,
+            TripDelay::Samples(n) => self.pending_samples.get() >= n,
+            TripDelay::Duration(d) => self
+                .pending_since
+                .get()
+                .is_some_and(|since| now.saturating_duration_since(since) >= d),
+        };
+        if expired {
+            self.trip(now);
+        } else {
+            self.state.set(InterlockState::Pending);
+        }
+    }
+
+    /// assert the interlock
+    fn trip(&self, now: C::Instant) {
+        self.state.set(InterlockState::Active);
+        self.acked.set(false);
+        self.tripped_at.set(Some(now));
+        self.pending_since.set(None);
+        self.pending_samples.set(0);
+        self.observer.on_trip();
+    }
+
+    /// keep track of how long the inner value has been continuously clear
+    fn track_clear(&self, now: C::Instant) {
+        match self.inner.is_clear() {
+            true => {
+                if self.clear_since.get().is_none() {
+                    self.clear_since.set(Some(now));
+                }
+            }
+            false => self.clear_since.set(None),
+        }
+    }
+
+    /// reset an active interlock whose inner value is clear, if the latch mode allows it
+    fn update_auto_reset(&self, now: C::Instant) {
+        if !self.inner.is_clear() {
+            return;
+        }
+        let reset = match self.latch_mode {
+            LatchMode::Latched => false,
+            LatchMode::AutoReset => true,
+            LatchMode::AutoResetAfter(d) => self
+                .clear_since
+                .get()
+                .is_some_and(|since| now.saturating_duration_since(since) >= d),
+        };
+        if reset {
+            self.reset();
+        }
+    }
+
+    /// return the interlock to inactive
+    fn reset(&self) {
+        let was_active = self.state.get() == InterlockState::Active;
+        self.cancel_pending();
+        self.state.set(InterlockState::Inactive);
+        if was_active {
+            self.observer.on_clear();
+        }
+    }
+
+    /// drop a pending trip, the inner value went clear before the delay ran out
+    fn cancel_pending(&self) {
+        self.pending_since.set(None);
+        self.pending_samples.set(0);
+        if self.state.get() == InterlockState::Pending {
+            self.state.set(InterlockState::Inactive);
+        }
+    }
+
+    /// get the alarm state of the interlock
+    pub fn get_alarm_state(&self) -> AlarmState {
+        if self.suppressed.get() {
+            return AlarmState::Suppressed;
+        }
+        if self.shelved.get() {
+            return AlarmState::Shelved;
+        }
+        // apply any pending transitions, but look past a bypass: it must never hide a latched
+        // trip from the alarm
+        self.get_state();
+        match (self.state.get(), self.acked.get()) {
+            (InterlockState::Active, false) => AlarmState::TrippedUnacked,
+            (InterlockState::Active, true) => AlarmState::TrippedAcked,
+            (_, false) => AlarmState::RtnUnacked,
+            (_, true) => AlarmState::Normal,
+        }
+    }
+
+    /// acknowledge the current trip. this doesn't clear the interlock, that is still up to
+    /// [`Interlock::try_clear_interlock`]
+    pub fn acknowledge(&self) {
+        self.acked.set(true);
+    }
+
+    /// take the alarm out of service until [`Interlock::unshelve`]. the interlock keeps tripping
+    /// as usual, only the alarm state reports [`AlarmState::Shelved`]
+    pub fn shelve(&self) {
+        self.shelved.set(true);
+    }
+
+    /// return a shelved alarm to service. a trip that is still active is annunciated again
+    pub fn unshelve(&self) {
+        if self.shelved.replace(false) {
+            self.reannunciate();
+        }
+    }
+
+    /// suppress the alarm by design, e.g. while the equipment is out of service. the interlock
+    /// keeps tripping as usual, only the alarm state reports [`AlarmState::Suppressed`]
+    pub fn suppress(&self) {
+        self.suppressed.set(true);
+    }
+
+    /// lift a suppression. a trip that is still active is annunciated again
+    pub fn unsuppress(&self) {
+        if self.suppressed.replace(false) {
+            self.reannunciate();
+        }
+    }
+
+    /// make an active trip unacknowledged again after the alarm comes back into service
+    fn reannunciate(&self) {
+        self.get_state();
+        if self.state.get() == InterlockState::Active {
+            self.acked.set(false);
+        }
+    }
+
+    /// get the time the interlock last asserted, if it is active. `None` for a trip restored with
+    /// [`Interlock::with_restored_state`]
+    pub fn tripped_at(&self) -> Option<C::Instant> {
+        match self.state.get() {
+            InterlockState::Active => self.tripped_at.get(),
+            InterlockState::Inactive | InterlockState::Pending | InterlockState::Bypassed => None,
+        }
+    }
+
+    /// get the clock the interlock measures time with
+    pub fn clock(&self) -> &C {
+        &self.clock
     }
 
     /// get a ref of the inner value
     pub fn get_inner_ref(&self) -> &T {
-        todo!("Work around & vs Ref<'_, T>");
+        &self.inner
+    }
+}
+
+impl<T, C, A, O> Interlock<T, C, A, O>
+where
+    T: Interlockable + Clone,
+    C: Clock,
+    A: Authorize,
+    O: Observer<T::UpdateType>,
+{
+    /// get a clone of the inner value
+    pub fn get_inner(&self) -> T {
+        self.inner.clone()
     }
 }
 
 #[cfg(test)]
 mod tests {
+    extern crate std;
 
     use super::*;
+    use std::sync::{Mutex, MutexGuard};
+
+    static BYPASSES: Mutex<()> = Mutex::new(());
+
+    /// held by every test that starts a bypass, so tests can check exact changes of
+    /// [`active_bypasses`] while other tests run
+    pub(crate) fn lock_bypasses() -> MutexGuard<'static, ()> {
+        // a failed test still ended its bypasses when its interlocks were dropped
+        BYPASSES.lock().unwrap_or_else(|e| e.into_inner())
+    }
 
     #[derive(Clone)]
-    struct InterlockableBool {
+    pub(crate) struct InterlockableBool {
         val: Cell<bool>,
     }
     impl InterlockableBool {
-        fn new(val: bool) -> Self {
+        pub(crate) const fn new(val: bool) -> Self {
             Self {
                 val: Cell::new(val),
             }
@@ -156,6 +778,168 @@ mod tests {
         assert_eq!(i1.get_state(), InterlockState::Active);
     }
 
+    #[test]
+    /// test that the interlock records when it asserted, according to its clock
+    fn tripped_at() {
+        let clock = time::ManualClock::new(0);
+        let i1 = Interlock::with_clock(InterlockableBool::new(false), &clock);
+        assert_eq!(i1.tripped_at(), None);
+        clock.advance(3);
+        i1.set(true);
+        clock.advance(3);
+        i1.set(true);
+        assert_eq!(i1.tripped_at(), Some(3));
+        i1.set(false);
+        assert_eq!(i1.try_clear_interlock(), Ok(()));
+        assert_eq!(i1.tripped_at(), None);
+    }
+
+    #[test]
+    /// test that a sample based trip delay ignores glitches shorter than the delay
+    fn trip_delay_samples() {
+        let i1 =
+            Interlock::new(InterlockableBool::new(false)).with_trip_delay(TripDelay::Samples(3));
+        i1.set(true);
+        assert_eq!(i1.get_state(), InterlockState::Pending);
+        i1.set(false);
+        assert_eq!(i1.get_state(), InterlockState::Inactive);
+
+        i1.set(true);
+        i1.set(true);
+        assert_eq!(i1.get_state(), InterlockState::Pending);
+        i1.set(true);
+        assert_eq!(i1.get_state(), InterlockState::Active);
+    }
+
+    #[test]
+    /// test that a time based trip delay asserts once it runs out, even without another `set`
+    fn trip_delay_duration() {
+        let clock = time::ManualClock::new(0);
+        let i1 = Interlock::with_clock(InterlockableBool::new(false), &clock)
+            .with_trip_delay(TripDelay::Duration(10));
+        i1.set(true);
+        clock.advance(9);
+        assert_eq!(i1.get_state(), InterlockState::Pending);
+        clock.advance(1);
+        assert_eq!(i1.get_state(), InterlockState::Active);
+        assert_eq!(i1.tripped_at(), Some(10));
+    }
+
+    #[test]
+    /// test that polling in between samples doesn't count as more samples
+    fn trip_delay_samples_poll() {
+        let i1 =
+            Interlock::new(InterlockableBool::new(false)).with_trip_delay(TripDelay::Samples(3));
+        i1.set(true);
+        i1.poll();
+        i1.poll();
+        assert_eq!(i1.get_state(), InterlockState::Pending);
+        i1.set(true);
+        i1.poll();
+        assert_eq!(i1.get_state(), InterlockState::Pending);
+        i1.set(true);
+        assert_eq!(i1.get_state(), InterlockState::Active);
+    }
+
+    #[test]
+    /// test that a pending trip is dropped if the inner value went clear behind the interlock's
+    /// back before the trip delay ran out
+    fn trip_delay_inner_cleared() {
+        let clock = time::ManualClock::new(0);
+        let i1 = Interlock::with_clock(InterlockableBool::new(false), &clock)
+            .with_trip_delay(TripDelay::Duration(10));
+        i1.set(true);
+        i1.get_inner_ref().set(false);
+        clock.advance(10);
+        assert_eq!(i1.get_state(), InterlockState::Inactive);
+        assert_eq!(i1.tripped_at(), None);
+    }
+
+    #[test]
+    /// test that a reset hold-off refuses to clear until the inner value has been clear for
+    /// long enough, and reports how long is left
+    fn reset_holdoff() {
+        let clock = time::ManualClock::new(0);
+        let i1 =
+            Interlock::with_clock(InterlockableBool::new(false), &clock).with_reset_holdoff(10);
+        i1.set(true);
+        clock.advance(5);
+        i1.set(false);
+        clock.advance(4);
+        assert_eq!(
+            i1.try_clear_interlock(),
+            Err(Error::HoldOff { remaining: 6 })
+        );
+
+        // going bad again restarts the hold-off
+        i1.set(true);
+        i1.set(false);
+        clock.advance(9);
+        assert_eq!(
+            i1.try_clear_interlock(),
+            Err(Error::HoldOff { remaining: 1 })
+        );
+        clock.advance(1);
+        assert_eq!(i1.try_clear_interlock(), Ok(()));
+        assert_eq!(i1.get_state(), InterlockState::Inactive);
+    }
+
+    #[test]
+    /// test that an auto reset interlock follows its inner value
+    fn auto_reset() {
+        let i1 =
+            Interlock::new(InterlockableBool::new(false)).with_latch_mode(LatchMode::AutoReset);
+        i1.set(true);
+        assert_eq!(i1.get_state(), InterlockState::Active);
+        i1.set(false);
+        assert_eq!(i1.get_state(), InterlockState::Inactive);
+    }
+
+    #[test]
+    /// test that a delayed auto reset waits for the inner value to be clear for long enough
+    fn auto_reset_after() {
+        let clock = time::ManualClock::new(0);
+        let i1 = Interlock::with_clock(InterlockableBool::new(false), &clock)
+            .with_latch_mode(LatchMode::AutoResetAfter(5));
+        i1.set(true);
+        i1.set(false);
+        clock.advance(4);
+        assert_eq!(i1.get_state(), InterlockState::Active);
+        clock.advance(1);
+        assert_eq!(i1.get_state(), InterlockState::Inactive);
+    }
+
+    /// an inner type that can't be cloned, like a peripheral wrapper
+    struct Peripheral {
+        fault: Cell<bool>,
+    }
+
+    impl Interlockable for Peripheral {
+        type UpdateType = bool;
+        fn is_clear(&self) -> bool {
+            !self.fault.get()
+        }
+
+        fn set(&self, new: Self::UpdateType) {
+            self.fault.set(new);
+        }
+
+        fn clear(&self, new: Self::UpdateType) {
+            self.fault.set(new);
+        }
+    }
+
+    #[test]
+    /// test that non clonable inner types can be interlocked and borrowed
+    fn borrow_inner() {
+        let i1 = Interlock::new(Peripheral {
+            fault: Cell::new(false),
+        });
+        i1.set(true);
+        assert!(!i1.get_inner_ref().is_clear());
+        assert_eq!(i1.get_state(), InterlockState::Active);
+    }
+
     #[test]
     /// test that the boolean conversions haven't changed
     fn interlock_state_boolean_conversion() {
@@ -163,5 +947,44 @@ mod tests {
         assert!(b1);
         let b2: bool = InterlockState::Inactive.into();
         assert!(!b2);
+        let b3: bool = InterlockState::Pending.into();
+        assert!(!b3);
+    }
+
+    #[derive(interlock_rs_derive::Interlockable)]
+    #[interlock(clear_when = "self.closed.get() && self.locked.get()")]
+    struct Door {
+        closed: Cell<bool>,
+        locked: Cell<bool>,
+        #[interlock(skip)]
+        _name: &'static str,
+    }
+
+    #[derive(interlock_rs_derive::Interlockable)]
+    #[interlock(clear_fn = "below_limit")]
+    struct Level(Cell<u16>, #[interlock(skip)] u16);
+
+    fn below_limit(level: &Level) -> bool {
+        level.0.get() < level.1
+    }
+
+    #[test]
+    /// test that derived inner types update every field, and check the given predicate
+    fn derive_interlockable() {
+        let door = Interlock::new(Door {
+            closed: Cell::new(true),
+            locked: Cell::new(true),
+            _name: "north",
+        });
+        door.set((true, false));
+        assert_eq!(door.get_state(), InterlockState::Active);
+        door.clear((true, true));
+        assert_eq!(door.try_clear_interlock(), Ok(()));
+
+        let level = Interlock::new(Level(Cell::new(0), 100));
+        level.set(99);
+        assert_eq!(level.get_state(), InterlockState::Inactive);
+        level.set(100);
+        assert_eq!(level.get_state(), InterlockState::Active);
     }
 }
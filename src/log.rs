@@ -0,0 +1,263 @@
+//! a fixed size event log.
+//!
+//! [`EventLog`] is a ring buffer of timestamped [`Event`]s that keeps the most recent `N` events
+//! for post-incident review. it uses interior mutability like the interlocks themselves, so many
+//! interlocks (and groups, through [`EventLog::record`]) can write into the same log.
+//!
+//! interlocks write into a log through an [`Observer`]:
+//! * [`EventLogger`] records every transition
+//! * [`SnapshotLogger`] also records the value that was last set on the inner value, for inner
+//!   types whose update type is `Copy`
+
+use core::cell::Cell;
+
+use crate::time::Clock;
+use crate::{Error, Observer};
+
+/// identifies which interlock an event came from
+pub type InterlockId = u16;
+
+/// what happened
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EventKind {
+    /// the interlock asserted
+    Trip,
+    /// the interlock went from active to inactive
+    Clear,
+    /// an attempt to clear the interlock was refused
+    ClearRefused(Error),
+    /// the interlock was bypassed
+    Bypass { reason: &'static str },
+    /// a bypass expired or was removed
+    BypassEnd,
+}
+
+/// a single entry in an [`EventLog`]. I is the clock's instant type, V the value snapshot type
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Event<I, V> {
+    pub kind: EventKind,
+    pub id: InterlockId,
+    pub timestamp: I,
+    /// the value last set on the interlock, if the logger takes snapshots
+    pub value: Option<V>,
+}
+
+/// a ring buffer of the `N` most recent events
+pub struct EventLog<I: Copy, V: Copy, const N: usize> {
+    entries: [Cell<Option<Event<I, V>>>; N],
+    /// index the next event is written to
+    next: Cell<usize>,
+    /// total number of events ever recorded
+    recorded: Cell<u32>,
+}
+
+impl<I: Copy, V: Copy, const N: usize> EventLog<I, V, N> {
+    pub const fn new() -> Self {
+        Self {
+            entries: [const { Cell::new(None) }; N],
+            next: Cell::new(0),
+            recorded: Cell::new(0),
+        }
+    }
+
+    /// record an event, overwriting the oldest one if the log is full
+    pub fn record(&self, event: Event<I, V>) {
+        let Some(slot) = self.entries.get(self.next.get()) else {
+            return;
+        };
+        slot.set(Some(event));
+        self.next.set((self.next.get() + 1) % N);
+        self.recorded.set(self.recorded.get().saturating_add(1));
+    }
+
+    /// the number of events in the log
+    pub fn len(&self) -> usize {
+        (self.recorded.get() as usize).min(N)
+    }
+
+    /// return true if the log is empty
+    pub fn is_empty(&self) -> bool {
+        self.recorded.get() == 0
+    }
+
+    /// the number of events that were overwritten before anyone read them out
+    pub fn overwritten(&self) -> u32 {
+        self.recorded.get().saturating_sub(N as u32)
+    }
+
+    /// empty the log
+    pub fn clear(&self) {
+        for entry in self.entries.iter() {
+            entry.set(None);
+        }
+        self.next.set(0);
+        self.recorded.set(0);
+    }
+
+    /// iterate over the events in the log, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = Event<I, V>> + '_ {
+        let start = match self.recorded.get() as usize >= N {
+            true => self.next.get(),
+            false => 0,
+        };
+        (0..self.len()).filter_map(move |i| self.entries[(start + i) % N].get())
+    }
+}
+
+impl<I: Copy, V: Copy, const N: usize> Default for EventLog<I, V, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// an [`Observer`] that records every transition of an interlock into an [`EventLog`],
+/// timestamped with `clock`
+pub struct EventLogger<'a, C: Clock, const N: usize> {
+    log: &'a EventLog<C::Instant, (), N>,
+    clock: C,
+    id: InterlockId,
+}
+
+impl<'a, C: Clock, const N: usize> EventLogger<'a, C, N> {
+    pub const fn new(log: &'a EventLog<C::Instant, (), N>, clock: C, id: InterlockId) -> Self {
+        Self { log, clock, id }
+    }
+
+    fn record(&self, kind: EventKind) {
+        self.log.record(Event {
+            kind,
+            id: self.id,
+            timestamp: self.clock.now(),
+            value: None,
+        });
+    }
+}
+
+impl<U, C: Clock, const N: usize> Observer<U> for EventLogger<'_, C, N> {
+    fn on_trip(&self) {
+        self.record(EventKind::Trip);
+    }
+    fn on_clear(&self) {
+        self.record(EventKind::Clear);
+    }
+    fn on_clear_refused(&self, error: Error) {
+        self.record(EventKind::ClearRefused(error));
+    }
+    fn on_bypass(&self, reason: &'static str) {
+        self.record(EventKind::Bypass { reason });
+    }
+    fn on_bypass_end(&self) {
+        self.record(EventKind::BypassEnd);
+    }
+}
+
+/// an [`Observer`] like [`EventLogger`] that also records the value last set on the interlock
+/// with each event
+pub struct SnapshotLogger<'a, C: Clock, U: Copy, const N: usize> {
+    log: &'a EventLog<C::Instant, U, N>,
+    clock: C,
+    id: InterlockId,
+    last: Cell<Option<U>>,
+}
+
+impl<'a, C: Clock, U: Copy, const N: usize> SnapshotLogger<'a, C, U, N> {
+    pub const fn new(log: &'a EventLog<C::Instant, U, N>, clock: C, id: InterlockId) -> Self {
+        Self {
+            log,
+            clock,
+            id,
+            last: Cell::new(None),
+        }
+    }
+
+    fn record(&self, kind: EventKind) {
+        self.log.record(Event {
+            kind,
+            id: self.id,
+            timestamp: self.clock.now(),
+            value: self.last.get(),
+        });
+    }
+}
+
+impl<C: Clock, U: Copy, const N: usize> Observer<U> for SnapshotLogger<'_, C, U, N> {
+    fn on_update(&self, value: &U) {
+        self.last.set(Some(*value));
+    }
+    fn on_trip(&self) {
+        self.record(EventKind::Trip);
+    }
+    fn on_clear(&self) {
+        self.record(EventKind::Clear);
+    }
+    fn on_clear_refused(&self, error: Error) {
+        self.record(EventKind::ClearRefused(error));
+    }
+    fn on_bypass(&self, reason: &'static str) {
+        self.record(EventKind::Bypass { reason });
+    }
+    fn on_bypass_end(&self) {
+        self.record(EventKind::BypassEnd);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::InterlockableBool;
+    use crate::time::ManualClock;
+    use crate::Interlock;
+
+    #[test]
+    /// test that the ring buffer keeps the most recent events, oldest first
+    fn ring_buffer() {
+        let log: EventLog<u64, (), 2> = EventLog::new();
+        assert!(log.is_empty());
+        for t in 0..3 {
+            log.record(Event {
+                kind: EventKind::Trip,
+                id: 0,
+                timestamp: t,
+                value: None,
+            });
+        }
+        assert_eq!(log.len(), 2);
+        assert_eq!(log.overwritten(), 1);
+        let mut timestamps = log.iter().map(|e| e.timestamp);
+        assert_eq!(timestamps.next(), Some(1));
+        assert_eq!(timestamps.next(), Some(2));
+        assert_eq!(timestamps.next(), None);
+    }
+
+    #[test]
+    /// test that interlocks log trips, refusals, clears and bypasses with value snapshots
+    fn snapshot_logger() {
+        let _bypasses = crate::tests::lock_bypasses();
+        let clock = ManualClock::new(0);
+        let log: EventLog<u64, bool, 8> = EventLog::new();
+        let i1 = Interlock::with_clock(InterlockableBool::new(false), &clock)
+            .with_observer(SnapshotLogger::new(&log, &clock, 7));
+        clock.advance(1);
+        i1.set(true);
+        let _ = i1.try_clear_interlock();
+        clock.advance(1);
+        i1.clear(false);
+        i1.try_clear_interlock().unwrap();
+        i1.bypass(5, "test");
+
+        let events: [Event<u64, bool>; 4] = core::array::from_fn(|i| log.iter().nth(i).unwrap());
+        assert_eq!(
+            events[0],
+            Event {
+                kind: EventKind::Trip,
+                id: 7,
+                timestamp: 1,
+                value: Some(true)
+            }
+        );
+        assert_eq!(events[1].kind, EventKind::ClearRefused(Error::ClearError));
+        assert_eq!(events[2].kind, EventKind::Clear);
+        assert_eq!(events[2].value, Some(false));
+        assert_eq!(events[3].kind, EventKind::Bypass { reason: "test" });
+    }
+}
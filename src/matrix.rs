@@ -0,0 +1,164 @@
+//! cause and effect matrices.
+//!
+//! safety engineers usually specify interlocks as a cause and effect (C&E) matrix: each row is a
+//! cause (a condition that trips), each column is an effect (an action that the trip demands,
+//! like closing a valve). [`interlocks!`](crate::interlocks) turns such a matrix into plain code.
+
+/// declare a cause and effect matrix.
+///
+/// generates a struct with one [`Interlock`](crate::Interlock) field per cause (with the
+/// struct's visibility), a `const fn new` taking the inner values in declaration order, and an
+/// `effects` method that returns which effects are demanded. an effect is demanded while any
+/// cause in its column is tripped ([`InterlockState::Active`](crate::InterlockState)), so causes
+/// that are pending or bypassed don't demand anything.
+///
+/// the effects are returned as a struct of `bool`s, one per effect, named in the declaration.
+/// the matrix is checked at compile time: a row or column that isn't declared doesn't build.
+///
+/// the generated struct holds plain [`Interlock`](crate::Interlock)s, which keep their state in
+/// `Cell`s, so it isn't `Sync` and can't be a `static` on its own. `new` is `const`, so it can
+/// still be built in a `static` behind a critical section, e.g.
+/// `static BOILER: critical_section::Mutex<Boiler> = Mutex::new(Boiler::new(..))`, or be handed
+/// to a single task through a cell crate like `static_cell`.
+///
+/// ```
+/// use core::cell::Cell;
+/// use interlock_rs::threshold::Threshold;
+/// use interlock_rs::{interlocks, Interlockable};
+///
+/// pub struct Switch(Cell<bool>);
+/// impl Interlockable for Switch {
+///     type UpdateType = bool;
+///     fn is_clear(&self) -> bool { !self.0.get() }
+///     fn set(&self, new: bool) { self.0.set(new) }
+///     fn clear(&self, new: bool) { self.0.set(new) }
+/// }
+///
+/// interlocks! {
+///     /// the boiler's shutdown logic
+///     pub struct Boiler {
+///         causes {
+///             high_pressure: Threshold<f32>,
+///             low_level: Threshold<f32>,
+///             estop: Switch,
+///         }
+///         effects BoilerEffects { fuel_valve, feed_pump, horn }
+///         matrix {
+///             high_pressure => [fuel_valve, horn],
+///             low_level => [fuel_valve, feed_pump],
+///             estop => [fuel_valve, feed_pump, horn],
+///         }
+///     }
+/// }
+///
+/// let boiler = Boiler::new(
+///     Threshold::high(10.0, 9.0),
+///     Threshold::low(1.0, 1.5),
+///     Switch(Cell::new(false)),
+/// );
+/// boiler.high_pressure.set(10.5);
+/// let demanded = boiler.effects();
+/// assert!(demanded.fuel_valve && demanded.horn && !demanded.feed_pump);
+/// ```
+#[macro_export]
+macro_rules! interlocks {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            causes {
+                $($(#[$c_meta:meta])* $cause:ident: $c_ty:ty),* $(,)?
+            }
+            effects $effects:ident {
+                $($(#[$e_meta:meta])* $effect:ident),* $(,)?
+            }
+            matrix {
+                $($row:ident => [$($col:ident),* $(,)?]),* $(,)?
+            }
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            $($(#[$c_meta])* $vis $cause: $crate::Interlock<$c_ty>,)*
+        }
+
+        /// the effects demanded by the tripped causes
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+        $vis struct $effects {
+            $($(#[$e_meta])* $vis $effect: bool,)*
+        }
+
+        impl $effects {
+            /// return true if any effect is demanded
+            #[allow(dead_code)]
+            $vis fn any(&self) -> bool {
+                false $(|| self.$effect)*
+            }
+        }
+
+        impl $name {
+            #[allow(clippy::too_many_arguments)]
+            $vis const fn new($($cause: $c_ty),*) -> Self {
+                Self {
+                    $($cause: $crate::Interlock::new($cause),)*
+                }
+            }
+
+            /// get the effects demanded by the causes that are tripped right now
+            $vis fn effects(&self) -> $effects {
+                let mut effects = <$effects as ::core::default::Default>::default();
+                $(
+                    if self.$row.get_state() == $crate::InterlockState::Active {
+                        $(effects.$col = true;)*
+                    }
+                )*
+                effects
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::InterlockableBool;
+    use crate::threshold::Threshold;
+
+    interlocks! {
+        struct Press {
+            causes {
+                guard_open: InterlockableBool,
+                overload: Threshold<u16>,
+            }
+            effects PressEffects { stop_ram, brake, light }
+            matrix {
+                guard_open => [stop_ram, light],
+                overload => [stop_ram, brake],
+            }
+        }
+    }
+
+    #[test]
+    /// test that effects follow the latched causes linked to them
+    fn effects() {
+        let press = Press::new(InterlockableBool::new(false), Threshold::high(500, 450));
+        assert!(!press.effects().any());
+
+        press.overload.set(510);
+        let expected = PressEffects {
+            stop_ram: true,
+            brake: true,
+            light: false,
+        };
+        assert_eq!(press.effects(), expected);
+
+        // still latched after the load drops
+        press.overload.set(400);
+        assert_eq!(press.effects(), expected);
+        press.guard_open.set(true);
+        assert!(press.effects().light);
+
+        press.guard_open.clear(false);
+        assert_eq!(press.guard_open.try_clear_interlock(), Ok(()));
+        assert_eq!(press.overload.try_clear_interlock(), Ok(()));
+        assert_eq!(press.effects(), PressEffects::default());
+    }
+}
@@ -0,0 +1,148 @@
+//! a critical section protected interlock for single core MCUs.
+//!
+//! [`MutexInterlock`] wraps an [`Interlock<T>`] and runs every operation inside a critical
+//! section, so the whole read inner / check `is_clear` / update state sequence is atomic with
+//! respect to interrupts. unlike [`SyncInterlock`](crate::SyncInterlock) this needs no atomics at
+//! all, which makes it usable on Cortex-M0, AVR and RISC-V cores without the `A` extension.
+//!
+//! the critical section itself is supplied by the [`CriticalSection`] trait. on most targets it
+//! is a one liner on top of the `critical-section` crate:
+//!
+//! ```ignore
+//! struct Cs;
+//! unsafe impl interlock_rs::CriticalSection for Cs {
+//!     fn with<R>(f: impl FnOnce() -> R) -> R {
+//!         critical_section::with(|_| f())
+//!     }
+//! }
+//!
+//! static DOOR: MutexInterlock<Cs, Door> = MutexInterlock::new(Door::new());
+//! ```
+
+use core::marker::PhantomData;
+
+use crate::{Error, Interlock, InterlockState, Interlockable};
+
+/// a provider of critical sections.
+///
+/// # Safety
+/// implementors must guarantee that no other call to `with` (from any interrupt or thread that
+/// can touch the same [`MutexInterlock`]) runs while `f` is running.
+pub unsafe trait CriticalSection {
+    /// run `f` inside a critical section
+    fn with<R>(f: impl FnOnce() -> R) -> R;
+}
+
+/// an [`Interlock<T>`] that can live in a `static`, with all access serialized through the
+/// critical section `CS`
+pub struct MutexInterlock<CS, T: Interlockable> {
+    interlock: Interlock<T>,
+    _cs: PhantomData<fn() -> CS>,
+}
+
+// SAFETY: every access to `interlock` goes through `CS::with`, which guarantees exclusive access
+// for its duration. `T` must be `Send` since it may be touched from a different context than the
+// one that created it.
+unsafe impl<CS, T> Sync for MutexInterlock<CS, T>
+where
+    CS: CriticalSection,
+    T: Interlockable + Send,
+{
+}
+
+impl<CS, T> MutexInterlock<CS, T>
+where
+    CS: CriticalSection,
+    T: Interlockable,
+{
+    pub const fn new(inner: T) -> Self {
+        Self {
+            interlock: Interlock::new(inner),
+            _cs: PhantomData,
+        }
+    }
+
+    /// attempt to clear the interlock. Returns:
+    ///   * Ok(()) if clearing the interlock was successful
+    ///   * Err(Error::ClearError) if clearing the interlock was unsuccessful
+    pub fn try_clear_interlock(&self) -> Result<(), Error> {
+        CS::with(|| self.interlock.try_clear_interlock())
+    }
+
+    /// sets the inner value, and asserts the interlock if the inner value is no longer clear
+    pub fn set(&self, new_value: T::UpdateType) {
+        CS::with(|| self.interlock.set(new_value))
+    }
+
+    /// clear the inner value with an update type
+    pub fn clear(&self, new_value: T::UpdateType) {
+        CS::with(|| self.interlock.clear(new_value))
+    }
+
+    /// get the state of the interlock
+    pub fn get_state(&self) -> InterlockState {
+        CS::with(|| self.interlock.get_state())
+    }
+
+    /// run `f` with a ref of the inner value, inside the critical section. the ref can't escape
+    /// `f`, since nothing protects it once the critical section ends
+    pub fn with_inner<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        CS::with(|| f(self.interlock.get_inner_ref()))
+    }
+}
+
+impl<CS, T> MutexInterlock<CS, T>
+where
+    CS: CriticalSection,
+    T: Interlockable + Clone,
+{
+    /// get a clone of the inner value
+    pub fn get_inner(&self) -> T {
+        CS::with(|| self.interlock.get_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::InterlockableBool;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    static SECTIONS: AtomicUsize = AtomicUsize::new(0);
+
+    /// counts the number of critical sections entered. only sound because each test uses its
+    /// own interlock and never touches it from more than one thread
+    struct CountingCs;
+    unsafe impl CriticalSection for CountingCs {
+        fn with<R>(f: impl FnOnce() -> R) -> R {
+            SECTIONS.fetch_add(1, Ordering::SeqCst);
+            f()
+        }
+    }
+
+    static SHARED: MutexInterlock<CountingCs, InterlockableBool> =
+        MutexInterlock::new(InterlockableBool::new(false));
+
+    #[test]
+    /// test that the mutex interlock behaves like the interlock it wraps, and that every
+    /// operation runs inside a critical section
+    fn set_and_clear() {
+        let i1: MutexInterlock<CountingCs, _> = MutexInterlock::new(InterlockableBool::new(false));
+        let before = SECTIONS.load(Ordering::SeqCst);
+        i1.set(true);
+        assert_eq!(i1.get_state(), InterlockState::Active);
+        assert_eq!(i1.try_clear_interlock(), Err(Error::ClearError));
+        i1.clear(false);
+        assert!(i1.with_inner(|inner| inner.is_clear()));
+        assert_eq!(i1.try_clear_interlock(), Ok(()));
+        assert_eq!(i1.get_state(), InterlockState::Inactive);
+        assert!(SECTIONS.load(Ordering::SeqCst) - before >= 7);
+    }
+
+    #[test]
+    /// test that a mutex interlock can be shared through a static
+    fn shared_static() {
+        SHARED.set(true);
+        assert_eq!(SHARED.get_state(), InterlockState::Active);
+    }
+}
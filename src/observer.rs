@@ -0,0 +1,163 @@
+//! transition callbacks.
+//!
+//! an [`Interlock`](crate::Interlock) calls its [`Observer`] at every transition, so sirens,
+//! logging and telemetry can be driven straight from the point where the interlock trips or
+//! clears instead of polling [`Interlock::get_state`](crate::Interlock::get_state). every method
+//! has an empty default, and the default [`NoObserver`] compiles away entirely. several
+//! observers can be attached to one interlock as a tuple, e.g. `(siren, logger)`, which calls
+//! each of them in order.
+
+use crate::Error;
+
+/// callbacks for interlock transitions. U is the update type of the interlock's inner value
+pub trait Observer<U> {
+    /// a new value is about to be set on the inner value, through `set` or `clear`
+    fn on_update(&self, _value: &U) {}
+    /// the interlock asserted
+    fn on_trip(&self) {}
+    /// the interlock went from active to inactive, either through a reset or by itself
+    fn on_clear(&self) {}
+    /// an attempt to clear the interlock was refused
+    fn on_clear_refused(&self, _error: Error) {}
+    /// the interlock was bypassed
+    fn on_bypass(&self, _reason: &'static str) {}
+    /// a bypass expired or was removed
+    fn on_bypass_end(&self) {}
+}
+
+/// no observer at all. this is the default
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct NoObserver;
+
+impl<U> Observer<U> for NoObserver {}
+
+impl<U, O: Observer<U>> Observer<U> for &O {
+    fn on_update(&self, value: &U) {
+        (*self).on_update(value)
+    }
+    fn on_trip(&self) {
+        (*self).on_trip()
+    }
+    fn on_clear(&self) {
+        (*self).on_clear()
+    }
+    fn on_clear_refused(&self, error: Error) {
+        (*self).on_clear_refused(error)
+    }
+    fn on_bypass(&self, reason: &'static str) {
+        (*self).on_bypass(reason)
+    }
+    fn on_bypass_end(&self) {
+        (*self).on_bypass_end()
+    }
+}
+
+/// forward every callback to each observer of a tuple, in order
+macro_rules! tuple_observer {
+    ($($o:ident: $i:tt),+) => {
+        impl<U, $($o: Observer<U>),+> Observer<U> for ($($o,)+) {
+            fn on_update(&self, value: &U) {
+                $(self.$i.on_update(value);)+
+            }
+            fn on_trip(&self) {
+                $(self.$i.on_trip();)+
+            }
+            fn on_clear(&self) {
+                $(self.$i.on_clear();)+
+            }
+            fn on_clear_refused(&self, error: Error) {
+                $(self.$i.on_clear_refused(error);)+
+            }
+            fn on_bypass(&self, reason: &'static str) {
+                $(self.$i.on_bypass(reason);)+
+            }
+            fn on_bypass_end(&self) {
+                $(self.$i.on_bypass_end();)+
+            }
+        }
+    };
+}
+
+tuple_observer!(A: 0, B: 1);
+tuple_observer!(A: 0, B: 1, C: 2);
+tuple_observer!(A: 0, B: 1, C: 2, D: 3);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::InterlockableBool;
+    use crate::{AccessLevel, Interlock, LatchMode, RequireLevel};
+    use core::cell::Cell;
+
+    #[derive(Default)]
+    struct Counts {
+        trips: Cell<u32>,
+        clears: Cell<u32>,
+        refused: Cell<Option<Error>>,
+    }
+
+    impl Observer<bool> for Counts {
+        fn on_trip(&self) {
+            self.trips.set(self.trips.get() + 1);
+        }
+        fn on_clear(&self) {
+            self.clears.set(self.clears.get() + 1);
+        }
+        fn on_clear_refused(&self, error: Error) {
+            self.refused.set(Some(error));
+        }
+    }
+
+    #[test]
+    /// test that trips, clears and refusals are reported once per transition
+    fn transitions() {
+        let counts = Counts::default();
+        let i1 = Interlock::new(InterlockableBool::new(false)).with_observer(&counts);
+        i1.set(true);
+        i1.set(true);
+        assert_eq!(counts.trips.get(), 1);
+
+        assert!(i1.try_clear_interlock().is_err());
+        assert_eq!(counts.refused.get(), Some(Error::ClearError));
+
+        i1.set(false);
+        i1.try_clear_interlock().unwrap();
+        i1.try_clear_interlock().unwrap();
+        assert_eq!(counts.clears.get(), 1);
+    }
+
+    #[test]
+    /// test that auto resets and unauthorized resets are reported too
+    fn auto_reset_and_unauthorized() {
+        let counts = Counts::default();
+        let i1 = Interlock::new(InterlockableBool::new(false))
+            .with_latch_mode(LatchMode::AutoReset)
+            .with_observer(&counts);
+        i1.set(true);
+        i1.set(false);
+        assert_eq!(counts.clears.get(), 1);
+
+        let i2 = Interlock::new(InterlockableBool::new(false))
+            .with_authorizer(RequireLevel(AccessLevel::SUPERVISOR))
+            .with_observer(&counts);
+        assert!(i2.try_clear_interlock_with(&AccessLevel::OPERATOR).is_err());
+        assert_eq!(counts.refused.get(), Some(Error::Unauthorized));
+    }
+
+    #[test]
+    /// test that every observer of a tuple is told about every transition
+    fn tuple() {
+        let a = Counts::default();
+        let b = Counts::default();
+        let i1 = Interlock::new(InterlockableBool::new(false)).with_observer((&a, &b));
+        i1.set(true);
+        assert!(i1.try_clear_interlock().is_err());
+        i1.set(false);
+        i1.try_clear_interlock().unwrap();
+        for counts in [&a, &b] {
+            assert_eq!(counts.trips.get(), 1);
+            assert_eq!(counts.clears.get(), 1);
+            assert_eq!(counts.refused.get(), Some(Error::ClearError));
+        }
+    }
+}
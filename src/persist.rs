@@ -0,0 +1,443 @@
+//! persistence of latched state across reboots.
+//!
+//! if a controller browns out while an interlock is active, [`Interlock::new`](crate::Interlock)
+//! comes back up inactive and the latch is silently lost. [`FlashStore`] keeps a log of the
+//! latched state and recent trips in NOR flash, so it can be restored on power up with
+//! [`Interlock::with_restored_state`](crate::Interlock::with_restored_state).
+//!
+//! records are appended round robin over a region of flash sectors, so every sector is erased
+//! equally often, and each record carries a CRC so torn writes are ignored. the newest valid
+//! record always holds the current latched state, and the sector holding it is never erased.
+//!
+//! the [`ReadNorFlash`] / [`NorFlash`] traits mirror the ones in `embedded-storage`'s `nor_flash`
+//! module, so any `embedded-storage` flash driver can be plugged in with a thin newtype.
+
+use crate::InterlockState;
+
+/// read access to NOR flash
+pub trait ReadNorFlash {
+    type Error;
+    /// the minimum number of bytes that can be read at once
+    const READ_SIZE: usize;
+    /// read `bytes.len()` bytes starting at `offset`
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error>;
+    /// the size of the flash in bytes
+    fn capacity(&self) -> usize;
+}
+
+/// write and erase access to NOR flash. erased flash reads as `0xFF`, and writes can only clear
+/// bits
+pub trait NorFlash: ReadNorFlash {
+    /// the minimum number of bytes that can be written at once
+    const WRITE_SIZE: usize;
+    /// the size of an erasable sector
+    const ERASE_SIZE: usize;
+    /// erase every sector in `from..to`
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error>;
+    /// write `bytes` starting at `offset`
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error>;
+}
+
+const RECORD_SIZE: usize = 16;
+const MAGIC: u8 = 0xA5;
+
+/// what a record says happened
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RecordKind {
+    /// the latched state changed
+    State = 0,
+    /// the interlock tripped
+    Trip = 1,
+}
+
+/// a decoded record
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Record {
+    kind: RecordKind,
+    state: InterlockState,
+    seq: u32,
+    timestamp: u32,
+}
+
+impl Record {
+    /// layout: magic, kind, state, 0xFF, seq (LE), timestamp (LE), 0xFFFF, crc16 (LE)
+    fn encode(&self) -> [u8; RECORD_SIZE] {
+        let mut b = [0xFF; RECORD_SIZE];
+        b[0] = MAGIC;
+        b[1] = self.kind as u8;
+        b[2] = self.state as u8;
+        b[4..8].copy_from_slice(&self.seq.to_le_bytes());
+        b[8..12].copy_from_slice(&self.timestamp.to_le_bytes());
+        let crc = crc16(&b[..14]);
+        b[14..16].copy_from_slice(&crc.to_le_bytes());
+        b
+    }
+
+    fn decode(b: &[u8; RECORD_SIZE]) -> Option<Self> {
+        if b[0] != MAGIC || crc16(&b[..14]).to_le_bytes() != b[14..16] {
+            return None;
+        }
+        let kind = match b[1] {
+            0 => RecordKind::State,
+            1 => RecordKind::Trip,
+            _ => return None,
+        };
+        Some(Self {
+            kind,
+            state: InterlockState::from_u8(b[2]),
+            seq: u32::from_le_bytes([b[4], b[5], b[6], b[7]]),
+            timestamp: u32::from_le_bytes([b[8], b[9], b[10], b[11]]),
+        })
+    }
+}
+
+/// return true if sequence number `a` is newer than `b`. sequence numbers wrap, and the records
+/// in flash never span more than half their range, so the difference decides
+fn newer(a: u32, b: u32) -> bool {
+    (a.wrapping_sub(b) as i32) > 0
+}
+
+/// CRC-16/CCITT-FALSE
+fn crc16(bytes: &[u8]) -> u16 {
+    let mut crc: u16 = 0xFFFF;
+    for byte in bytes {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            crc = match crc & 0x8000 {
+                0 => crc << 1,
+                _ => (crc << 1) ^ 0x1021,
+            };
+        }
+    }
+    crc
+}
+
+/// errors writing to a [`FlashStore`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StoreError<E> {
+    /// the flash driver failed
+    Flash(E),
+    /// no blank slot could be found for the record, even after erasing, so nothing was
+    /// written. the flash is likely worn out
+    NoBlankSlot,
+}
+
+impl<E> From<E> for StoreError<E> {
+    fn from(e: E) -> Self {
+        StoreError::Flash(e)
+    }
+}
+
+/// a log of the latched state and trips of one interlock, kept in a region of NOR flash
+pub struct FlashStore<F: NorFlash> {
+    flash: F,
+    base: u32,
+    size: u32,
+    /// offset (relative to `base`) of the next record
+    next: u32,
+    seq: u32,
+    latched: InterlockState,
+}
+
+impl<F: NorFlash> FlashStore<F> {
+    /// open the store in the `sectors` flash sectors starting at `base`, finding the newest
+    /// record. panics if the region is smaller than two sectors, doesn't start on a sector
+    /// boundary or doesn't fit in the flash, or if the flash's read / write / erase sizes don't
+    /// line up with the 16 byte records
+    pub fn mount(mut flash: F, base: u32, sectors: u32) -> Result<Self, F::Error> {
+        assert!(sectors >= 2, "need at least two sectors to rotate through");
+        assert!(
+            RECORD_SIZE.is_multiple_of(F::READ_SIZE) && RECORD_SIZE.is_multiple_of(F::WRITE_SIZE)
+        );
+        assert!(F::ERASE_SIZE.is_multiple_of(RECORD_SIZE));
+
+        let size = u32::try_from(F::ERASE_SIZE)
+            .ok()
+            .and_then(|erase| sectors.checked_mul(erase));
+        let end = size.and_then(|size| base.checked_add(size));
+        assert!(
+            end.and_then(|end| usize::try_from(end).ok())
+                .is_some_and(|end| end <= flash.capacity()),
+            "region doesn't fit in the flash"
+        );
+        assert!(
+            (base as usize).is_multiple_of(F::ERASE_SIZE),
+            "region doesn't start on a sector boundary"
+        );
+        // checked above
+        let size = size.unwrap_or_default();
+        let mut newest: Option<(u32, Record)> = None;
+        let mut offset = 0;
+        while offset < size {
+            let mut b = [0; RECORD_SIZE];
+            flash.read(base + offset, &mut b)?;
+            if let Some(r) = Record::decode(&b) {
+                if newest.is_none_or(|(_, n)| newer(r.seq, n.seq)) {
+                    newest = Some((offset, r));
+                }
+            }
+            offset += RECORD_SIZE as u32;
+        }
+
+        let (next, seq, latched) = match newest {
+            Some((offset, r)) => ((offset + RECORD_SIZE as u32) % size, r.seq, r.state),
+            None => (0, 0, InterlockState::Inactive),
+        };
+        Ok(Self {
+            flash,
+            base,
+            size,
+            next,
+            seq,
+            latched,
+        })
+    }
+
+    /// the latched state as of the newest record, or [`InterlockState::Inactive`] for a blank
+    /// store
+    pub fn latched_state(&self) -> InterlockState {
+        self.latched
+    }
+
+    /// record a change of the latched state. `timestamp` is whatever the application uses as a
+    /// persistent time base, e.g. RTC seconds
+    pub fn record_state(
+        &mut self,
+        state: InterlockState,
+        timestamp: u32,
+    ) -> Result<(), StoreError<F::Error>> {
+        self.append(RecordKind::State, state, timestamp)
+    }
+
+    /// record a trip. this also records the latched state as [`InterlockState::Active`]
+    pub fn record_trip(&mut self, timestamp: u32) -> Result<(), StoreError<F::Error>> {
+        self.append(RecordKind::Trip, InterlockState::Active, timestamp)
+    }
+
+    /// read the timestamps of the most recent trips still in flash into `out`, newest first.
+    /// returns how many were found, which is at most 32
+    pub fn recent_trips(&mut self, out: &mut [u32]) -> Result<usize, F::Error> {
+        let mut seqs = [0u32; 32];
+        let capacity = out.len().min(seqs.len());
+        let mut found = 0;
+        let mut offset = 0;
+        while offset < self.size {
+            let mut b = [0; RECORD_SIZE];
+            self.flash.read(self.base + offset, &mut b)?;
+            offset += RECORD_SIZE as u32;
+            let Some(r) = Record::decode(&b).filter(|r| r.kind == RecordKind::Trip) else {
+                continue;
+            };
+
+            // insertion sort, newest first, keeping at most `capacity`
+            let pos = seqs[..found]
+                .iter()
+                .position(|s| newer(r.seq, *s))
+                .unwrap_or(found);
+            if pos >= capacity {
+                continue;
+            }
+            let end = found.min(capacity - 1);
+            seqs.copy_within(pos..end, pos + 1);
+            out.copy_within(pos..end, pos + 1);
+            seqs[pos] = r.seq;
+            out[pos] = r.timestamp;
+            found = (found + 1).min(capacity);
+        }
+        Ok(found)
+    }
+
+    /// give the flash back
+    pub fn into_inner(self) -> F {
+        self.flash
+    }
+
+    fn append(
+        &mut self,
+        kind: RecordKind,
+        state: InterlockState,
+        timestamp: u32,
+    ) -> Result<(), StoreError<F::Error>> {
+        let record = Record {
+            kind,
+            state,
+            seq: self.seq.wrapping_add(1),
+            timestamp,
+        };
+
+        // skip over slots that aren't blank, e.g. left over from a torn write
+        for _ in 0..self.size / RECORD_SIZE as u32 {
+            let offset = self.next;
+            self.next = (self.next + RECORD_SIZE as u32) % self.size;
+            if offset.is_multiple_of(F::ERASE_SIZE as u32) {
+                self.flash.erase(
+                    self.base + offset,
+                    self.base + offset + F::ERASE_SIZE as u32,
+                )?;
+            }
+            let mut b = [0; RECORD_SIZE];
+            self.flash.read(self.base + offset, &mut b)?;
+            if b.iter().all(|b| *b == 0xFF) {
+                self.flash.write(self.base + offset, &record.encode())?;
+                self.seq = record.seq;
+                self.latched = state;
+                return Ok(());
+            }
+        }
+        Err(StoreError::NoBlankSlot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::InterlockableBool;
+    use crate::Interlock;
+
+    /// an in memory NOR flash, with 64 byte sectors
+    struct MemFlash {
+        data: [u8; 256],
+        erases: [u32; 4],
+        /// erasing doesn't do anything, like on worn out flash
+        worn: bool,
+    }
+
+    impl MemFlash {
+        fn new() -> Self {
+            Self {
+                data: [0xFF; 256],
+                erases: [0; 4],
+                worn: false,
+            }
+        }
+    }
+
+    impl ReadNorFlash for MemFlash {
+        type Error = ();
+        const READ_SIZE: usize = 1;
+        fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+            let offset = offset as usize;
+            bytes.copy_from_slice(&self.data[offset..offset + bytes.len()]);
+            Ok(())
+        }
+        fn capacity(&self) -> usize {
+            self.data.len()
+        }
+    }
+
+    impl NorFlash for MemFlash {
+        const WRITE_SIZE: usize = 4;
+        const ERASE_SIZE: usize = 64;
+        fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+            if !self.worn {
+                self.data[from as usize..to as usize].fill(0xFF);
+            }
+            self.erases[from as usize / Self::ERASE_SIZE] += 1;
+            Ok(())
+        }
+        fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+            for (d, b) in self.data[offset as usize..].iter_mut().zip(bytes) {
+                *d &= *b;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    /// test that a latched trip survives a "reboot" and is restored into a new interlock
+    fn restore_latched_state() {
+        let mut store = FlashStore::mount(MemFlash::new(), 0, 4).unwrap();
+        assert_eq!(store.latched_state(), InterlockState::Inactive);
+        store.record_trip(100).unwrap();
+
+        let store = FlashStore::mount(store.into_inner(), 0, 4).unwrap();
+        let i1 = Interlock::new(InterlockableBool::new(false))
+            .with_restored_state(store.latched_state());
+        assert_eq!(i1.get_state(), InterlockState::Active);
+        assert_eq!(i1.tripped_at(), None);
+    }
+
+    #[test]
+    /// test that records wrap around every sector, and that recent trips are read back newest
+    /// first
+    fn wear_levelling() {
+        let mut store = FlashStore::mount(MemFlash::new(), 0, 4).unwrap();
+        for t in 0..40 {
+            store.record_trip(t).unwrap();
+            store.record_state(InterlockState::Inactive, t).unwrap();
+        }
+        let mut trips = [0; 3];
+        assert_eq!(store.recent_trips(&mut trips).unwrap(), 3);
+        assert_eq!(trips, [39, 38, 37]);
+
+        let flash = store.into_inner();
+        assert!(flash.erases.iter().all(|e| *e >= 4));
+        let store = FlashStore::mount(flash, 0, 4).unwrap();
+        assert_eq!(store.latched_state(), InterlockState::Inactive);
+    }
+
+    #[test]
+    /// test that a torn record is ignored on mount, and skipped over on the next write
+    fn torn_write() {
+        let mut store = FlashStore::mount(MemFlash::new(), 0, 4).unwrap();
+        store.record_trip(1).unwrap();
+        let mut flash = store.into_inner();
+        flash.write(16, &[MAGIC, 0, 0, 0]).unwrap();
+
+        let mut store = FlashStore::mount(flash, 0, 4).unwrap();
+        assert_eq!(store.latched_state(), InterlockState::Active);
+        store.record_state(InterlockState::Inactive, 2).unwrap();
+        let store = FlashStore::mount(store.into_inner(), 0, 4).unwrap();
+        assert_eq!(store.latched_state(), InterlockState::Inactive);
+    }
+
+    #[test]
+    /// test that a record that can't be written is reported, rather than silently dropped
+    fn no_blank_slot() {
+        let flash = MemFlash {
+            data: [0; 256],
+            erases: [0; 4],
+            worn: true,
+        };
+        let mut store = FlashStore::mount(flash, 0, 4).unwrap();
+        assert_eq!(store.record_trip(1), Err(StoreError::NoBlankSlot));
+        assert_eq!(store.latched_state(), InterlockState::Inactive);
+    }
+
+    #[test]
+    #[should_panic(expected = "region doesn't fit in the flash")]
+    /// test that a region running past the end of the flash is refused up front
+    fn region_too_large() {
+        let _ = FlashStore::mount(MemFlash::new(), 64, 4);
+    }
+
+    #[test]
+    /// test that the newest record and the recent trips are still found after the sequence
+    /// number wraps
+    fn seq_wrap() {
+        let mut flash = MemFlash::new();
+        let records = [
+            (RecordKind::Trip, InterlockState::Active, u32::MAX - 1, 1),
+            (RecordKind::State, InterlockState::Active, u32::MAX, 2),
+            (RecordKind::Trip, InterlockState::Active, 0, 3),
+            (RecordKind::State, InterlockState::Inactive, 1, 4),
+        ];
+        for (i, (kind, state, seq, timestamp)) in records.into_iter().enumerate() {
+            let record = Record {
+                kind,
+                state,
+                seq,
+                timestamp,
+            };
+            flash
+                .write((i * RECORD_SIZE) as u32, &record.encode())
+                .unwrap();
+        }
+
+        let mut store = FlashStore::mount(flash, 0, 4).unwrap();
+        assert_eq!(store.latched_state(), InterlockState::Inactive);
+        let mut trips = [0; 4];
+        assert_eq!(store.recent_trips(&mut trips).unwrap(), 2);
+        assert_eq!(trips[..2], [3, 1]);
+    }
+}
@@ -0,0 +1,185 @@
+//! data quality.
+//!
+//! field values usually arrive with a quality flag from the bus or the I/O card, and a value
+//! flagged bad says nothing about the process. [`Qualified<T>`] wraps an [`Interlockable`] so its
+//! update type becomes `(value, quality)`, i.e. `Interlock<Qualified<T>>::set((v, q))`. good and
+//! uncertain values are passed through to `T`. bad and comm lost values are dropped, and the
+//! [`QualityPolicy`] decides what that does to the input.
+
+use core::cell::Cell;
+
+use crate::Interlockable;
+
+/// the quality of a value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quality {
+    Good,
+    /// usable, but e.g. out of calibration or over range. passed through like a good value
+    Uncertain,
+    /// the source flagged the value as invalid
+    Bad,
+    /// the value is stale, since the source can't be reached
+    CommLost,
+}
+
+impl Quality {
+    /// return true if a value of this quality can be trusted
+    pub fn is_usable(self) -> bool {
+        matches!(self, Quality::Good | Quality::Uncertain)
+    }
+}
+
+/// what a bad (or comm lost) value does to a [`Qualified`] input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QualityPolicy {
+    /// the input goes not clear right away
+    Trip,
+    /// the input keeps the state of the last usable value, for as long as it takes
+    HoldLast,
+    /// the input keeps the state of the last usable value for up to this many consecutive bad
+    /// values, and goes not clear on the one after
+    IgnoreFor(u32),
+}
+
+/// an [`Interlockable`] whose values carry a [`Quality`]. see the [module docs](self) for details
+pub struct Qualified<T: Interlockable> {
+    inner: T,
+    policy: QualityPolicy,
+    quality: Cell<Quality>,
+    bad_samples: Cell<u32>,
+}
+
+impl<T: Interlockable> Qualified<T> {
+    /// wrap `inner` with the given policy. the quality starts out good
+    pub const fn new(inner: T, policy: QualityPolicy) -> Self {
+        Self {
+            inner,
+            policy,
+            quality: Cell::new(Quality::Good),
+            bad_samples: Cell::new(0),
+        }
+    }
+
+    /// get the quality of the last value
+    pub fn quality(&self) -> Quality {
+        self.quality.get()
+    }
+
+    /// get the number of consecutive unusable values up to now
+    pub fn bad_samples(&self) -> u32 {
+        self.bad_samples.get()
+    }
+
+    /// get a ref of the wrapped input, which holds the last usable value
+    pub fn get_inner_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// return true if the policy has tripped the input because of bad quality
+    pub fn quality_tripped(&self) -> bool {
+        match self.policy {
+            QualityPolicy::Trip => self.bad_samples.get() > 0,
+            QualityPolicy::HoldLast => false,
+            QualityPolicy::IgnoreFor(n) => self.bad_samples.get() > n,
+        }
+    }
+
+    /// record the quality, and return the value if it is usable
+    fn qualify(&self, (value, quality): (T::UpdateType, Quality)) -> Option<T::UpdateType> {
+        self.quality.set(quality);
+        match quality.is_usable() {
+            true => {
+                self.bad_samples.set(0);
+                Some(value)
+            }
+            false => {
+                self.bad_samples
+                    .set(self.bad_samples.get().saturating_add(1));
+                None
+            }
+        }
+    }
+}
+
+impl<T: Interlockable> Interlockable for Qualified<T> {
+    type UpdateType = (T::UpdateType, Quality);
+
+    fn is_clear(&self) -> bool {
+        !self.quality_tripped() && self.inner.is_clear()
+    }
+
+    fn set(&self, new: Self::UpdateType) {
+        if let Some(value) = self.qualify(new) {
+            self.inner.set(value);
+        }
+    }
+
+    fn clear(&self, new: Self::UpdateType) {
+        if let Some(value) = self.qualify(new) {
+            self.inner.clear(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::InterlockableBool;
+    use crate::{Interlock, InterlockState};
+
+    #[test]
+    /// test that the trip policy trips on the first bad value, and that the interlock can be
+    /// cleared once good values are back
+    fn trip() {
+        let i1 = Interlock::new(Qualified::new(
+            InterlockableBool::new(false),
+            QualityPolicy::Trip,
+        ));
+        i1.set((false, Quality::Uncertain));
+        assert_eq!(i1.get_state(), InterlockState::Inactive);
+        i1.set((false, Quality::CommLost));
+        assert_eq!(i1.get_state(), InterlockState::Active);
+        assert!(i1.try_clear_interlock().is_err());
+
+        i1.set((false, Quality::Good));
+        assert_eq!(i1.try_clear_interlock(), Ok(()));
+    }
+
+    #[test]
+    /// test that bad values are dropped, and the last usable value decides the state
+    fn hold_last() {
+        let i1 = Interlock::new(Qualified::new(
+            InterlockableBool::new(false),
+            QualityPolicy::HoldLast,
+        ));
+        for _ in 0..100 {
+            i1.set((true, Quality::Bad));
+        }
+        assert_eq!(i1.get_state(), InterlockState::Inactive);
+        assert_eq!(i1.get_inner_ref().bad_samples(), 100);
+
+        i1.set((true, Quality::Good));
+        assert_eq!(i1.get_state(), InterlockState::Active);
+        i1.clear((false, Quality::Bad));
+        assert!(i1.try_clear_interlock().is_err());
+    }
+
+    #[test]
+    /// test that bad values are ridden through up to the limit
+    fn ignore_for() {
+        let i1 = Interlock::new(Qualified::new(
+            InterlockableBool::new(false),
+            QualityPolicy::IgnoreFor(2),
+        ));
+        i1.set((false, Quality::Bad));
+        i1.set((false, Quality::Bad));
+        assert_eq!(i1.get_state(), InterlockState::Inactive);
+        i1.set((false, Quality::Good));
+        i1.set((false, Quality::Bad));
+        i1.set((false, Quality::Bad));
+        assert_eq!(i1.get_state(), InterlockState::Inactive);
+        i1.set((false, Quality::Bad));
+        assert_eq!(i1.get_state(), InterlockState::Active);
+        assert_eq!(i1.get_inner_ref().quality(), Quality::Bad);
+    }
+}
@@ -0,0 +1,235 @@
+//! rate of change limits.
+//!
+//! some hazards are about how fast a signal moves rather than where it is: a pressure rise
+//! rate, a temperature ramp. [`RateOfChange`] is an [`Interlockable`] that takes timestamped
+//! samples, computes the slope over a time window, and is not clear while the slope exceeds a
+//! limit. timestamps are [`Instant`]s from the crate's [`time`](crate::time) abstraction, and
+//! rates are in units per [`RateDuration::as_f32`] of the clock (per tick, or per second).
+
+use core::cell::Cell;
+
+use crate::time::{Duration, Instant};
+use crate::Interlockable;
+
+/// a [`Duration`] that can be turned into a number to divide by. implemented for tick counts
+/// and [`core::time::Duration`], and needed by [`RateOfChange`] only
+pub trait RateDuration: Duration {
+    /// the duration as a number, in the clock's own unit (ticks for integer durations, seconds
+    /// for [`core::time::Duration`])
+    fn as_f32(&self) -> f32;
+}
+
+impl RateDuration for u32 {
+    fn as_f32(&self) -> f32 {
+        *self as f32
+    }
+}
+
+impl RateDuration for u64 {
+    fn as_f32(&self) -> f32 {
+        *self as f32
+    }
+}
+
+impl RateDuration for core::time::Duration {
+    fn as_f32(&self) -> f32 {
+        self.as_secs_f32()
+    }
+}
+
+/// which direction of change is limited
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    /// only rising faster than the limit trips
+    Rising,
+    /// only falling faster than the limit trips
+    Falling,
+    /// changing faster than the limit in either direction trips
+    Either,
+}
+
+/// what a [`RateOfChange`] reports while it has no slope, i.e. until it has two samples taken at
+/// different times
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NoSlope {
+    /// not clear, so a sensor that never gets going can't hide a fast change. this is the
+    /// default. the first sample can be fed in with [`Interlock::clear`](crate::Interlock::clear)
+    /// to keep it from tripping the interlock
+    NotClear,
+    /// clear
+    Clear,
+}
+
+/// a timestamped measurement
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sample<I> {
+    pub at: I,
+    pub value: f32,
+}
+
+/// an [`Interlockable`] rate of change limit over the last `N` samples.
+///
+/// the slope is taken between the newest sample and the oldest one that is still inside the
+/// window, so `N` should be large enough to cover the window at the sample rate. if the newest
+/// sample is the only one inside the window (samples come slower than the window, or after a
+/// gap), the slope is taken to the sample before it instead. until there is a slope at all, the
+/// input follows its [`NoSlope`] policy
+#[derive(Debug, Clone)]
+pub struct RateOfChange<I: Instant<Duration: RateDuration>, const N: usize> {
+    window: I::Duration,
+    limit: f32,
+    direction: Direction,
+    no_slope: NoSlope,
+    samples: [Cell<Option<Sample<I>>>; N],
+    next: Cell<usize>,
+    rate: Cell<Option<f32>>,
+}
+
+impl<I: Instant<Duration: RateDuration>, const N: usize> RateOfChange<I, N> {
+    /// limit the rate of change in `direction` to `limit`, measured over `window`
+    pub const fn new(window: I::Duration, limit: f32, direction: Direction) -> Self {
+        Self {
+            window,
+            limit,
+            direction,
+            no_slope: NoSlope::NotClear,
+            samples: [const { Cell::new(None) }; N],
+            next: Cell::new(0),
+            rate: Cell::new(None),
+        }
+    }
+
+    /// choose what the input reports while it has no slope yet
+    pub fn with_no_slope(mut self, no_slope: NoSlope) -> Self {
+        self.no_slope = no_slope;
+        self
+    }
+
+    /// get the slope computed at the last sample, if there was one
+    pub fn rate(&self) -> Option<f32> {
+        self.rate.get()
+    }
+
+    fn push(&self, sample: Sample<I>) {
+        if N == 0 {
+            return;
+        }
+        // samples going back in time are ignored, so the last one stored is the newest
+        let newest = self.samples[(self.next.get() + N - 1) % N].get();
+        if newest.is_some_and(|n| n.at.saturating_duration_since(sample.at) > I::Duration::ZERO) {
+            return;
+        }
+        self.samples[self.next.get()].set(Some(sample));
+        self.next.set((self.next.get() + 1) % N);
+
+        let age = |s: &Sample<I>| sample.at.saturating_duration_since(s.at);
+        let held = || self.samples.iter().filter_map(|s| s.get());
+        let anchor = held()
+            .filter(|s| age(s) > I::Duration::ZERO && age(s) <= self.window)
+            .max_by_key(age)
+            .or_else(|| held().filter(|s| age(s) > self.window).min_by_key(age));
+        let rate = anchor.map(|a| (sample.value - a.value) / age(&a).as_f32());
+        self.rate.set(rate);
+    }
+}
+
+impl<I: Instant<Duration: RateDuration>, const N: usize> Interlockable for RateOfChange<I, N> {
+    type UpdateType = Sample<I>;
+
+    fn is_clear(&self) -> bool {
+        match (self.rate.get(), self.direction) {
+            (None, _) => self.no_slope == NoSlope::Clear,
+            (Some(r), Direction::Rising) => r <= self.limit,
+            (Some(r), Direction::Falling) => -r <= self.limit,
+            (Some(r), Direction::Either) => r.abs() <= self.limit,
+        }
+    }
+
+    fn set(&self, new: Self::UpdateType) {
+        self.push(new);
+    }
+
+    fn clear(&self, new: Self::UpdateType) {
+        self.push(new);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::{Clock, ManualClock};
+    use crate::{Interlock, InterlockState};
+
+    #[test]
+    /// test that a fast rise trips, and a slow rise over the same distance doesn't
+    fn rising() {
+        let clock = ManualClock::new(0);
+        let roc: RateOfChange<u64, 8> = RateOfChange::new(10, 2.0, Direction::Rising);
+        let i1 = Interlock::with_clock(roc, &clock);
+        i1.clear(Sample {
+            at: clock.now(),
+            value: 0.0,
+        });
+        for v in [1.5, 3.0, 4.5] {
+            clock.advance(1);
+            i1.set(Sample {
+                at: clock.now(),
+                value: v,
+            });
+        }
+        clock.advance(1);
+        assert_eq!(i1.get_state(), InterlockState::Inactive);
+        assert_eq!(i1.get_inner_ref().rate(), Some(1.5));
+
+        i1.set(Sample {
+            at: clock.now(),
+            value: 20.0,
+        });
+        assert_eq!(i1.get_state(), InterlockState::Active);
+    }
+
+    #[test]
+    /// test that only samples inside the window count towards the slope, and that falling is
+    /// ignored by a rising limit
+    fn window() {
+        let roc: RateOfChange<u64, 8> = RateOfChange::new(2, 1.0, Direction::Either);
+        roc.set(Sample { at: 0, value: 0.0 });
+        roc.set(Sample { at: 10, value: 0.0 });
+        roc.set(Sample { at: 11, value: 1.0 });
+        assert_eq!(roc.rate(), Some(1.0));
+        assert!(roc.is_clear());
+        roc.set(Sample {
+            at: 12,
+            value: -4.0,
+        });
+        assert!(!roc.is_clear());
+
+        let rising: RateOfChange<u64, 8> = RateOfChange::new(2, 1.0, Direction::Rising);
+        rising.set(Sample { at: 0, value: 0.0 });
+        rising.set(Sample { at: 1, value: -4.0 });
+        assert!(rising.is_clear());
+    }
+
+    #[test]
+    /// test that samples slower than the window still give a slope, and that there is no
+    /// slope to hide behind before the second sample
+    fn slow_samples() {
+        let roc: RateOfChange<u64, 4> = RateOfChange::new(2, 1.0, Direction::Rising);
+        roc.set(Sample { at: 0, value: 0.0 });
+        assert_eq!(roc.rate(), None);
+        assert!(!roc.is_clear());
+        roc.set(Sample { at: 5, value: 2.0 });
+        assert!(roc.is_clear());
+        roc.set(Sample {
+            at: 10,
+            value: 802.0,
+        });
+        assert_eq!(roc.rate(), Some(160.0));
+        assert!(!roc.is_clear());
+
+        let lenient: RateOfChange<u64, 4> =
+            RateOfChange::new(2, 1.0, Direction::Rising).with_no_slope(NoSlope::Clear);
+        lenient.set(Sample { at: 0, value: 0.0 });
+        assert!(lenient.is_clear());
+    }
+}
@@ -0,0 +1,144 @@
+//! a thread / interrupt safe interlock.
+//!
+//! [`SyncInterlock<T>`] has the same API as [`Interlock<T>`](crate::Interlock), but keeps its
+//! state in an [`AtomicU8`] instead of a [`Cell`](core::cell::Cell), so it can live in a `static`
+//! and be touched from both an ISR and the main loop.
+//!
+//! only atomic `load` and `store` are used (no compare-and-swap), so this works on cores without
+//! atomic read-modify-write support (e.g. thumbv6m) without needing `portable-atomic`.
+//!
+//! # `Sync` bounds
+//! [`SyncInterlock<T>`] is `Sync` exactly when `T` is `Sync`. Since [`Interlockable`] only hands
+//! out `&self`, an inner type that should be shared across contexts needs to keep its own state in
+//! atomics (or behind some other `Sync` primitive) rather than in a `Cell`.
+
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use crate::{Error, InterlockState, Interlockable};
+
+/// an interlock whose state is stored in an atomic. see the [module docs](self) for details
+pub struct SyncInterlock<T: Interlockable> {
+    inner: T,
+    state: AtomicU8,
+}
+
+impl<T> SyncInterlock<T>
+where
+    T: Interlockable,
+{
+    pub const fn new(inner: T) -> Self {
+        Self {
+            inner,
+            state: AtomicU8::new(InterlockState::Inactive as u8),
+        }
+    }
+
+    /// attempt to clear the interlock. Returns:
+    ///   * Ok(()) if clearing the interlock was successful
+    ///   * Err(Error::ClearError) if clearing the interlock was unsuccessful
+    pub fn try_clear_interlock(&self) -> Result<(), Error> {
+        if !self.inner.is_clear() {
+            return Err(Error::ClearError);
+        }
+        self.state
+            .store(InterlockState::Inactive as u8, Ordering::SeqCst);
+
+        // a `set` from another context may have tripped the interlock between our check and
+        // our store. re-check now that the store is visible, and re-assert if so.
+        if !self.inner.is_clear() {
+            self.state
+                .store(InterlockState::Active as u8, Ordering::SeqCst);
+            return Err(Error::ClearError);
+        }
+        Ok(())
+    }
+
+    /// sets the inner value, and asserts the interlock if the inner value is no longer clear
+    pub fn set(&self, new_value: T::UpdateType) {
+        self.inner.set(new_value);
+
+        // asserting an already asserted interlock is a no-op, so a plain store is enough here
+        if !self.inner.is_clear() {
+            self.state
+                .store(InterlockState::Active as u8, Ordering::SeqCst);
+        }
+    }
+
+    /// clear the inner value with an update type
+    pub fn clear(&self, new_value: T::UpdateType) {
+        self.inner.clear(new_value);
+    }
+
+    /// get the state of the interlock
+    pub fn get_state(&self) -> InterlockState {
+        InterlockState::from_u8(self.state.load(Ordering::SeqCst))
+    }
+
+    /// get a ref of the inner value
+    pub fn get_inner_ref(&self) -> &T {
+        &self.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::sync::atomic::AtomicBool;
+
+    struct InterlockableAtomicBool {
+        val: AtomicBool,
+    }
+
+    impl InterlockableAtomicBool {
+        const fn new(val: bool) -> Self {
+            Self {
+                val: AtomicBool::new(val),
+            }
+        }
+    }
+
+    impl Interlockable for InterlockableAtomicBool {
+        type UpdateType = bool;
+        fn is_clear(&self) -> bool {
+            !self.val.load(Ordering::SeqCst)
+        }
+
+        fn set(&self, new: Self::UpdateType) {
+            self.val.store(new, Ordering::SeqCst);
+        }
+
+        fn clear(&self, new: Self::UpdateType) {
+            self.val.store(new, Ordering::SeqCst);
+        }
+    }
+
+    static SHARED: SyncInterlock<InterlockableAtomicBool> =
+        SyncInterlock::new(InterlockableAtomicBool::new(false));
+
+    #[test]
+    /// test that a sync interlock latches and clears the same way an [`Interlock`] does
+    fn set_and_clear() {
+        let i1 = SyncInterlock::new(InterlockableAtomicBool::new(false));
+        assert_eq!(i1.get_state(), InterlockState::Inactive);
+        i1.set(true);
+        assert_eq!(i1.get_state(), InterlockState::Active);
+        i1.set(false);
+        assert_eq!(i1.get_state(), InterlockState::Active);
+        assert_eq!(i1.try_clear_interlock(), Ok(()));
+        assert_eq!(i1.get_state(), InterlockState::Inactive);
+
+        i1.set(true);
+        assert_eq!(i1.try_clear_interlock(), Err(Error::ClearError));
+        assert_eq!(i1.get_state(), InterlockState::Active);
+    }
+
+    #[test]
+    /// test that a sync interlock can be shared through a static
+    fn shared_static() {
+        SHARED.set(true);
+        assert_eq!(SHARED.get_state(), InterlockState::Active);
+        SHARED.clear(false);
+        assert_eq!(SHARED.try_clear_interlock(), Ok(()));
+        assert_eq!(SHARED.get_state(), InterlockState::Inactive);
+    }
+}
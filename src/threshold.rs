@@ -0,0 +1,180 @@
+//! analog thresholds with hysteresis.
+//!
+//! [`Threshold`] is an [`Interlockable`] whose update type is the measurement itself, so a
+//! temperature or pressure interlock is just `Interlock<Threshold<f32>>`. separate trip and clear
+//! levels give it a deadband, so a measurement hovering around the limit doesn't chatter.
+//!
+//! measurements that can't be compared with the limits (e.g. `NaN`) are treated as not clear.
+
+use core::cell::Cell;
+use core::cmp::Ordering::{Equal, Greater, Less};
+
+use crate::Interlockable;
+
+/// a pair of levels: the input goes not clear at `trip`, and is clear again at `clear`.
+///
+/// `clear` belongs on the safe side of `trip`: at or below it for a high limit, at or above it
+/// for a low one. inverted levels don't give any hysteresis. the input stays not clear for as
+/// long as it is past `trip`, so it behaves as if `clear` were `trip`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limit<V> {
+    pub trip: V,
+    pub clear: V,
+}
+
+/// which side(s) of the limits trip the input
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ThresholdMode<V> {
+    /// not clear at or above `trip`, clear again at or below `clear`
+    High(Limit<V>),
+    /// not clear at or below `trip`, clear again at or above `clear`
+    Low(Limit<V>),
+    /// not clear outside of the `low` and `high` trip levels, clear again once back inside both
+    /// clear levels
+    Window { low: Limit<V>, high: Limit<V> },
+}
+
+/// an [`Interlockable`] measurement compared against limits with hysteresis
+#[derive(Debug, Clone)]
+pub struct Threshold<V: PartialOrd + Copy> {
+    mode: ThresholdMode<V>,
+    value: Cell<Option<V>>,
+    tripped: Cell<bool>,
+}
+
+impl<V: PartialOrd + Copy> Threshold<V> {
+    /// a threshold with the given mode. the input is clear until the first measurement
+    pub const fn new(mode: ThresholdMode<V>) -> Self {
+        Self {
+            mode,
+            value: Cell::new(None),
+            tripped: Cell::new(false),
+        }
+    }
+
+    /// not clear at or above `trip`, clear again at or below `clear`
+    pub const fn high(trip: V, clear: V) -> Self {
+        Self::new(ThresholdMode::High(Limit { trip, clear }))
+    }
+
+    /// not clear at or below `trip`, clear again at or above `clear`
+    pub const fn low(trip: V, clear: V) -> Self {
+        Self::new(ThresholdMode::Low(Limit { trip, clear }))
+    }
+
+    /// not clear outside of the trip levels, clear again inside the clear levels
+    pub const fn window(low: Limit<V>, high: Limit<V>) -> Self {
+        Self::new(ThresholdMode::Window { low, high })
+    }
+
+    /// get the last measurement
+    pub fn value(&self) -> Option<V> {
+        self.value.get()
+    }
+
+    fn update(&self, v: V) {
+        self.value.set(Some(v));
+        let tripped = self.tripped.get();
+
+        // incomparable values (`None`) trip, or stay tripped
+        let high_trip = |l: &Limit<V>| !matches!(v.partial_cmp(&l.trip), Some(Less));
+        let high_hold = |l: &Limit<V>| !matches!(v.partial_cmp(&l.clear), Some(Less | Equal));
+        let low_trip = |l: &Limit<V>| !matches!(v.partial_cmp(&l.trip), Some(Greater));
+        let low_hold = |l: &Limit<V>| !matches!(v.partial_cmp(&l.clear), Some(Greater | Equal));
+
+        // past the trip level is never clear, even if the clear level is (wrongly) past it too
+        let next = match &self.mode {
+            ThresholdMode::High(l) => high_trip(l) || (tripped && high_hold(l)),
+            ThresholdMode::Low(l) => low_trip(l) || (tripped && low_hold(l)),
+            ThresholdMode::Window { low, high } => {
+                low_trip(low) || high_trip(high) || (tripped && (low_hold(low) || high_hold(high)))
+            }
+        };
+        self.tripped.set(next);
+    }
+}
+
+impl<V: PartialOrd + Copy> Interlockable for Threshold<V> {
+    type UpdateType = V;
+
+    fn is_clear(&self) -> bool {
+        !self.tripped.get()
+    }
+
+    fn set(&self, new: Self::UpdateType) {
+        self.update(new);
+    }
+
+    fn clear(&self, new: Self::UpdateType) {
+        self.update(new);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Interlock, InterlockState, LatchMode};
+
+    #[test]
+    /// test that a high threshold doesn't chatter inside its deadband
+    fn high_hysteresis() {
+        let t = Threshold::high(100.0, 90.0);
+        t.set(99.0);
+        assert!(t.is_clear());
+        t.set(100.0);
+        assert!(!t.is_clear());
+        t.set(95.0);
+        assert!(!t.is_clear());
+        t.set(90.0);
+        assert!(t.is_clear());
+        t.set(f32::NAN);
+        assert!(!t.is_clear());
+    }
+
+    #[test]
+    /// test that inverted limits trip at the trip level, without any deadband
+    fn inverted_limits() {
+        let t = Threshold::high(100.0, 110.0);
+        t.set(100.0);
+        assert!(!t.is_clear());
+        t.set(105.0);
+        assert!(!t.is_clear());
+        t.set(99.0);
+        assert!(t.is_clear());
+
+        let t = Threshold::low(10, 5);
+        t.set(10);
+        assert!(!t.is_clear());
+        t.set(7);
+        assert!(!t.is_clear());
+        t.set(11);
+        assert!(t.is_clear());
+    }
+
+    #[test]
+    /// test a window threshold as an auto reset permissive
+    fn window() {
+        let i1 = Interlock::new(Threshold::window(
+            Limit {
+                trip: 10,
+                clear: 15,
+            },
+            Limit {
+                trip: 50,
+                clear: 45,
+            },
+        ))
+        .with_latch_mode(LatchMode::AutoReset);
+        i1.set(30);
+        assert_eq!(i1.get_state(), InterlockState::Inactive);
+        i1.set(9);
+        assert_eq!(i1.get_state(), InterlockState::Active);
+        i1.set(12);
+        assert_eq!(i1.get_state(), InterlockState::Active);
+        i1.set(15);
+        assert_eq!(i1.get_state(), InterlockState::Inactive);
+        i1.set(50);
+        assert_eq!(i1.get_state(), InterlockState::Active);
+        assert_eq!(i1.get_inner_ref().value(), Some(50));
+    }
+}
@@ -0,0 +1,189 @@
+//! a pluggable notion of time.
+//!
+//! the crate doesn't pick a time library. instead, an [`Interlock`](crate::Interlock) can be
+//! parameterised by anything that implements [`Clock`], which only needs to hand out an
+//! [`Instant`] that can measure a [`Duration`] to an earlier instant. wrapping `fugit`,
+//! `embedded-time` or embassy-time in a newtype that implements these traits is a few lines.
+//!
+//! * [`NoClock`] is the default, and has no notion of time at all
+//! * [`ManualClock`] is a clock that only moves when told to, for tests and simulations
+//! * `StdClock` (with the `std` feature) uses [`std::time::Instant`]
+//!
+//! `u32` and `u64` are instants too, as free running tick counters. they are allowed to wrap:
+//! elapsed time is measured modulo the counter's range, so it is only right for instants less
+//! than half the range apart. for a `u32` at 1 kHz, that is about 24.8 days, which is plenty for
+//! trip delays and hold-offs but means a `u32` tick count shouldn't be kept as a date.
+
+use core::cell::Cell;
+
+/// a span of time, as measured between two [`Instant`]s
+pub trait Duration: Copy + Ord {
+    /// a duration of no time at all
+    const ZERO: Self;
+    /// subtract `other` from `self`, stopping at [`Duration::ZERO`]
+    fn saturating_sub(self, other: Self) -> Self;
+}
+
+/// a point in time, as reported by a [`Clock`]. compare instants with
+/// [`Instant::saturating_duration_since`] rather than `Ord`, which doesn't know that tick
+/// counters wrap
+pub trait Instant: Copy + Ord {
+    type Duration: Duration;
+    /// the time elapsed from `earlier` to `self`, or [`Duration::ZERO`] if `earlier` is later
+    fn saturating_duration_since(&self, earlier: Self) -> Self::Duration;
+}
+
+/// a source of [`Instant`]s
+pub trait Clock {
+    type Instant: Instant;
+    /// get the current time
+    fn now(&self) -> Self::Instant;
+}
+
+/// the [`Duration`] type of a [`Clock`]
+pub type ClockDuration<C> = <<C as Clock>::Instant as Instant>::Duration;
+
+impl<C: Clock> Clock for &C {
+    type Instant = C::Instant;
+    fn now(&self) -> Self::Instant {
+        (*self).now()
+    }
+}
+
+/// a clock that doesn't exist. time never passes, and every duration is `()`
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct NoClock;
+
+impl Clock for NoClock {
+    type Instant = ();
+    fn now(&self) -> Self::Instant {}
+}
+
+impl Instant for () {
+    type Duration = ();
+    fn saturating_duration_since(&self, _earlier: Self) -> Self::Duration {}
+}
+
+impl Duration for () {
+    const ZERO: Self = ();
+    fn saturating_sub(self, _other: Self) -> Self {}
+}
+
+/// integer instants and durations are plain tick counts, see the [module docs](self) for
+/// wrapping
+macro_rules! impl_ticks {
+    ($($t:ty),*) => {
+        $(
+            impl Instant for $t {
+                type Duration = $t;
+                fn saturating_duration_since(&self, earlier: Self) -> Self::Duration {
+                    // more than half the range "elapsed" means `earlier` is really later
+                    let elapsed = self.wrapping_sub(earlier);
+                    match elapsed > <$t>::MAX / 2 {
+                        true => 0,
+                        false => elapsed,
+                    }
+                }
+            }
+
+            impl Duration for $t {
+                const ZERO: Self = 0;
+                fn saturating_sub(self, other: Self) -> Self {
+                    <$t>::saturating_sub(self, other)
+                }
+            }
+        )*
+    };
+}
+
+impl_ticks!(u32, u64);
+
+impl Duration for core::time::Duration {
+    const ZERO: Self = core::time::Duration::ZERO;
+    fn saturating_sub(self, other: Self) -> Self {
+        core::time::Duration::saturating_sub(self, other)
+    }
+}
+
+/// a clock that counts `u64` ticks, and only moves when told to
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    now: Cell<u64>,
+}
+
+impl ManualClock {
+    pub const fn new(start: u64) -> Self {
+        Self {
+            now: Cell::new(start),
+        }
+    }
+
+    /// move the clock forward by `ticks`
+    pub fn advance(&self, ticks: u64) {
+        self.now.set(self.now.get().saturating_add(ticks));
+    }
+
+    /// set the clock to `ticks`
+    pub fn set(&self, ticks: u64) {
+        self.now.set(ticks);
+    }
+}
+
+impl Clock for ManualClock {
+    type Instant = u64;
+    fn now(&self) -> Self::Instant {
+        self.now.get()
+    }
+}
+
+#[cfg(feature = "std")]
+mod std_clock {
+    use super::{Clock, Instant};
+
+    /// a clock backed by [`std::time::Instant`]
+    #[derive(Debug, Clone, Copy, Default, PartialEq)]
+    pub struct StdClock;
+
+    impl Clock for StdClock {
+        type Instant = std::time::Instant;
+        fn now(&self) -> Self::Instant {
+            std::time::Instant::now()
+        }
+    }
+
+    impl Instant for std::time::Instant {
+        type Duration = core::time::Duration;
+        fn saturating_duration_since(&self, earlier: Self) -> Self::Duration {
+            std::time::Instant::saturating_duration_since(self, earlier)
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+pub use std_clock::StdClock;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// test that the manual clock only moves when told to, and that durations saturate
+    fn manual_clock() {
+        let clock = ManualClock::new(10);
+        let start = clock.now();
+        assert_eq!(clock.now(), start);
+        clock.advance(5);
+        assert_eq!(clock.now().saturating_duration_since(start), 5);
+        assert_eq!(start.saturating_duration_since(clock.now()), 0);
+        assert_eq!(5u64.saturating_sub(7), Duration::ZERO);
+    }
+
+    #[test]
+    /// test that tick instants measure across the counter wrapping
+    fn tick_wrap() {
+        let before = u32::MAX - 2;
+        assert_eq!(7u32.saturating_duration_since(before), 10);
+        assert_eq!(before.saturating_duration_since(7), 0);
+        assert_eq!(3u64.saturating_duration_since(u64::MAX), 4);
+    }
+}
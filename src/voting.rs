@@ -0,0 +1,181 @@
+//! M-out-of-N voting.
+//!
+//! [`Voting`] combines `N` redundant [`Interlockable`] channels into a single [`Interlockable`]
+//! that is not clear when at least `M` channels are not clear (1oo2, 2oo3, ...), so it plugs
+//! straight into an [`Interlock`](crate::Interlock). channels can be marked faulty, in which case
+//! the configured [`DegradedMode`] decides how they take part in the vote.
+
+use core::cell::Cell;
+
+use crate::{IndexSet, Interlockable};
+
+/// how faulty channels take part in the vote
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DegradedMode {
+    /// a faulty channel always votes to trip. a 2oo3 vote with one faulty channel degrades to
+    /// 1oo2 on the healthy channels
+    TripVote,
+    /// a faulty channel is left out of the vote and `M` is kept. a 2oo3 vote with one faulty
+    /// channel degrades to 2oo2. if fewer than `M` healthy channels remain, the vote trips
+    Exclude,
+}
+
+/// an M-out-of-N vote over `N` channels. see the [module docs](self) for details
+#[derive(Clone)]
+pub struct Voting<T: Interlockable, const N: usize> {
+    channels: [T; N],
+    m: usize,
+    mode: DegradedMode,
+    faulty: Cell<IndexSet<N>>,
+}
+
+impl<T, const N: usize> Voting<T, N>
+where
+    T: Interlockable,
+{
+    /// create an `m`-out-of-`N` vote. panics if `m` is not in `1..=N`
+    pub const fn new(m: usize, mode: DegradedMode, channels: [T; N]) -> Self {
+        assert!(m >= 1 && m <= N, "M must be in 1..=N");
+        Self {
+            channels,
+            m,
+            mode,
+            faulty: Cell::new(IndexSet::new()),
+        }
+    }
+
+    /// get a ref of a channel
+    pub fn channel(&self, index: usize) -> Option<&T> {
+        self.channels.get(index)
+    }
+
+    /// mark a channel as faulty
+    pub fn mark_faulty(&self, index: usize) {
+        let mut faulty = self.faulty.get();
+        faulty.insert(index);
+        self.faulty.set(faulty);
+    }
+
+    /// mark a channel as healthy again
+    pub fn mark_healthy(&self, index: usize) {
+        let mut faulty = self.faulty.get();
+        faulty.remove(index);
+        self.faulty.set(faulty);
+    }
+
+    /// get the set of faulty channels
+    pub fn faulty(&self) -> IndexSet<N> {
+        self.faulty.get()
+    }
+
+    /// get the set of healthy channels that are not clear
+    pub fn tripped_channels(&self) -> IndexSet<N> {
+        let faulty = self.faulty.get();
+        (0..N)
+            .filter(|i| !faulty.contains(*i) && !self.channels[*i].is_clear())
+            .collect()
+    }
+
+    /// get the set of healthy channels that disagree with the outcome of the vote. a channel
+    /// that keeps showing up here is a candidate for calibration or replacement
+    pub fn disagreement(&self) -> IndexSet<N> {
+        let tripped = !self.is_clear();
+        let faulty = self.faulty.get();
+        (0..N)
+            .filter(|i| !faulty.contains(*i) && self.channels[*i].is_clear() == tripped)
+            .collect()
+    }
+}
+
+impl<T, const N: usize> Interlockable for Voting<T, N>
+where
+    T: Interlockable,
+{
+    /// the channel index to update, and the value to update it with. out of range indices are
+    /// ignored
+    type UpdateType = (usize, T::UpdateType);
+
+    fn is_clear(&self) -> bool {
+        let faulty = self.faulty.get().len();
+        let tripped = self.tripped_channels().len();
+        match self.mode {
+            DegradedMode::TripVote => tripped + faulty < self.m,
+            DegradedMode::Exclude => tripped < self.m && N - faulty >= self.m,
+        }
+    }
+
+    fn set(&self, (index, new): Self::UpdateType) {
+        if let Some(channel) = self.channels.get(index) {
+            channel.set(new);
+        }
+    }
+
+    fn clear(&self, (index, new): Self::UpdateType) {
+        if let Some(channel) = self.channels.get(index) {
+            channel.clear(new);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::InterlockableBool;
+    use crate::{Interlock, InterlockState};
+
+    fn two_out_of_three(mode: DegradedMode) -> Voting<InterlockableBool, 3> {
+        Voting::new(
+            2,
+            mode,
+            [
+                InterlockableBool::new(false),
+                InterlockableBool::new(false),
+                InterlockableBool::new(false),
+            ],
+        )
+    }
+
+    #[test]
+    /// test that a 2oo3 vote only trips the interlock once two channels agree
+    fn two_out_of_three_trips() {
+        let i1 = Interlock::new(two_out_of_three(DegradedMode::TripVote));
+        i1.set((0, true));
+        assert_eq!(i1.get_state(), InterlockState::Inactive);
+        assert!(i1.get_inner().disagreement().contains(0));
+
+        i1.set((2, true));
+        assert_eq!(i1.get_state(), InterlockState::Active);
+        let disagreement = i1.get_inner().disagreement();
+        assert!(disagreement.contains(1));
+        assert_eq!(disagreement.len(), 1);
+    }
+
+    #[test]
+    /// test that a faulty channel degrades 2oo3 to 1oo2 when it votes to trip
+    fn degrade_trip_vote() {
+        let vote = two_out_of_three(DegradedMode::TripVote);
+        vote.mark_faulty(1);
+        assert!(vote.is_clear());
+        vote.set((0, true));
+        assert!(!vote.is_clear());
+        vote.mark_healthy(1);
+        assert!(vote.is_clear());
+    }
+
+    #[test]
+    /// test that excluding faulty channels degrades 2oo3 to 2oo2, and trips once too few
+    /// healthy channels remain
+    fn degrade_exclude() {
+        let vote = two_out_of_three(DegradedMode::Exclude);
+        vote.mark_faulty(1);
+        vote.set((0, true));
+        assert!(vote.is_clear());
+        vote.set((2, true));
+        assert!(!vote.is_clear());
+
+        vote.set((0, false));
+        vote.set((2, false));
+        vote.mark_faulty(2);
+        assert!(!vote.is_clear());
+    }
+}
@@ -0,0 +1,131 @@
+//! heartbeat watchdogs.
+//!
+//! [`Watchdog`] is an [`Interlockable`] that is kicked with a sequence counter, e.g. from the
+//! heartbeat of a remote controller. it is not clear when no kick arrives within the timeout (a
+//! silent sender), or when the counter repeats or jumps (a frozen or restarted sender). since a
+//! silent sender never calls [`Interlock::set`](crate::Interlock::set), the interlock has to be
+//! polled with [`Interlock::poll`](crate::Interlock::poll) to notice the timeout.
+
+use core::cell::Cell;
+
+use crate::time::{Clock, ClockDuration, Instant};
+use crate::Interlockable;
+
+/// why a watchdog is not clear
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WatchdogFault {
+    /// no kick arrived within the timeout, or none arrived yet
+    Timeout,
+    /// the counter didn't move since the last kick
+    Repeated,
+    /// the counter skipped ahead (or went backwards)
+    Jumped,
+}
+
+/// an [`Interlockable`] heartbeat watchdog. kicks carry a `u32` sequence counter that has to
+/// increase by exactly one (wrapping) every time. the first kick may carry any counter value
+pub struct Watchdog<C: Clock> {
+    clock: C,
+    timeout: ClockDuration<C>,
+    last_kick: Cell<Option<C::Instant>>,
+    last_seq: Cell<Option<u32>>,
+    bad_seq: Cell<Option<WatchdogFault>>,
+}
+
+impl<C: Clock> Watchdog<C> {
+    /// a watchdog that times out `timeout` after the last kick. it starts out timed out, until
+    /// the first kick
+    pub const fn new(clock: C, timeout: ClockDuration<C>) -> Self {
+        Self {
+            clock,
+            timeout,
+            last_kick: Cell::new(None),
+            last_seq: Cell::new(None),
+            bad_seq: Cell::new(None),
+        }
+    }
+
+    /// get the reason the watchdog is not clear, if it isn't
+    pub fn fault(&self) -> Option<WatchdogFault> {
+        let timed_out = self
+            .last_kick
+            .get()
+            .is_none_or(|kick| self.clock.now().saturating_duration_since(kick) >= self.timeout);
+        match timed_out {
+            true => Some(WatchdogFault::Timeout),
+            false => self.bad_seq.get(),
+        }
+    }
+
+    fn kick(&self, seq: u32) {
+        self.last_kick.set(Some(self.clock.now()));
+        let fault = match self.last_seq.replace(Some(seq)) {
+            None => None,
+            Some(last) if seq == last => Some(WatchdogFault::Repeated),
+            Some(last) if seq != last.wrapping_add(1) => Some(WatchdogFault::Jumped),
+            Some(_) => None,
+        };
+        self.bad_seq.set(fault);
+    }
+}
+
+impl<C: Clock> Interlockable for Watchdog<C> {
+    /// the sequence counter carried by the kick
+    type UpdateType = u32;
+
+    fn is_clear(&self) -> bool {
+        self.fault().is_none()
+    }
+
+    fn set(&self, new: Self::UpdateType) {
+        self.kick(new);
+    }
+
+    fn clear(&self, new: Self::UpdateType) {
+        self.kick(new);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::ManualClock;
+    use crate::{Interlock, InterlockState};
+
+    #[test]
+    /// test that a silent sender trips the interlock once it is polled past the timeout
+    fn timeout() {
+        let clock = ManualClock::new(0);
+        let i1 = Interlock::new(Watchdog::new(&clock, 10));
+        assert_eq!(i1.get_inner_ref().fault(), Some(WatchdogFault::Timeout));
+        i1.set(0);
+        for seq in 1..5 {
+            clock.advance(5);
+            i1.set(seq);
+        }
+        assert_eq!(i1.get_state(), InterlockState::Inactive);
+
+        clock.advance(9);
+        i1.poll();
+        assert_eq!(i1.get_state(), InterlockState::Inactive);
+        clock.advance(1);
+        i1.poll();
+        assert_eq!(i1.get_state(), InterlockState::Active);
+    }
+
+    #[test]
+    /// test that a frozen or skipping counter is detected even with kicks arriving on time
+    fn sequence() {
+        let clock = ManualClock::new(0);
+        let dog = Watchdog::new(&clock, 10);
+        dog.set(u32::MAX);
+        dog.set(0);
+        assert!(dog.is_clear());
+        dog.set(0);
+        assert_eq!(dog.fault(), Some(WatchdogFault::Repeated));
+        dog.set(1);
+        assert!(dog.is_clear());
+        dog.set(5);
+        assert_eq!(dog.fault(), Some(WatchdogFault::Jumped));
+    }
+}